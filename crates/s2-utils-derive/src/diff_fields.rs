@@ -0,0 +1,100 @@
+use proc_macro::TokenStream;
+use quote::Tokens;
+
+use syn::*;
+
+/*
+Syntax
+
+#[derive(DiffFields)]
+struct S {
+  id: i32,
+  name: String,
+  #[diff_fields(skip)]
+  updated_at: i64,
+}
+
+*/
+
+struct FieldConfig {
+  name: String,
+  access: Tokens,
+}
+
+pub fn derive(input: TokenStream) -> TokenStream {
+  let ast: DeriveInput = parse(input).unwrap();
+  let ident = ast.ident;
+  let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+  let fields = match ast.data {
+    Data::Struct(DataStruct { ref fields, .. }) => parse_fields(fields),
+    _ => panic!("#[derive(DiffFields)] only supports struct"),
+  };
+
+  let checks: Vec<_> = fields
+    .iter()
+    .map(|FieldConfig { name, access }| {
+      quote! {
+        if self.#access != other.#access {
+          fields.push(#name);
+        }
+      }
+    })
+    .collect();
+
+  let tokens = quote! {
+    impl #impl_generics ::s2_utils::list::DiffFields for #ident #ty_generics #where_clause {
+      #[allow(unused_mut)]
+      fn diff_fields(&self, other: &Self) -> Vec<&'static str> {
+        let mut fields = vec![];
+        #(#checks)*
+        fields
+      }
+    }
+  };
+
+  tokens.into()
+}
+
+fn parse_fields(fields: &Fields) -> Vec<FieldConfig> {
+  fields
+    .iter()
+    .enumerate()
+    .filter(|&(_, field)| !is_skipped(field))
+    .map(|(i, field)| match field.ident {
+      Some(ref ident) => FieldConfig {
+        name: ident.as_ref().to_string(),
+        access: quote! { #ident },
+      },
+      None => {
+        let index = Index::from(i);
+        FieldConfig {
+          name: i.to_string(),
+          access: quote! { #index },
+        }
+      }
+    })
+    .collect()
+}
+
+fn is_skipped(field: &Field) -> bool {
+  field
+    .attrs
+    .iter()
+    .filter_map(|a| match a.interpret_meta() {
+      Some(Meta::List(MetaList {
+        ref ident,
+        ref nested,
+        ..
+      }))
+        if ident == "diff_fields" =>
+      {
+        Some(nested.iter().all(|item| match *item {
+          NestedMeta::Meta(Meta::Word(ref ident)) if ident == "skip" => true,
+          _ => panic!("#[diff_fields(..)] only 'skip' is supported"),
+        }))
+      }
+      _ => None,
+    })
+    .any(|skip| skip)
+}
@@ -1,5 +1,6 @@
 use proc_macro::TokenStream;
 
+use syn::spanned::Spanned;
 use syn::*;
 use quote::{ToTokens, Tokens};
 
@@ -16,18 +17,32 @@ struct Tuple(i32)
 #[has_item_key(i32, expr="self.id")]
 struct S { id: i32 }
 
+// key type is the field type, or a tuple of the field types
+#[derive(HasItemKey)]
+struct Line { #[item_key] order_id: i32, #[item_key] sku: String, qty: i32 }
+
+// borrows the field: `HasItemKeyRef<String>` and `HasItemKey<&'a String>` for
+// `&'a Sku`, so the list functions take `&[Sku]` with borrowed keys
+#[derive(HasItemKeyRef)]
+struct Sku { #[item_key] sku: String }
+
+// borrows the field as another type, through `Borrow`: `HasItemKeyRef<str>`
+// and `HasItemKey<&'a str>` for `&'a Sku`
+#[derive(HasItemKeyRef)]
+struct Sku { #[item_key(borrow = "str")] sku: String }
+
 */
 
 struct Config {
   ty: Tokens,
-  expr: Expr,
+  expr: Tokens,
 }
 
 pub fn derive(input: TokenStream) -> TokenStream {
   let ast: DeriveInput = parse(input).unwrap();
   let ident = ast.ident;
 
-  let configs: Vec<_> = ast
+  let mut configs: Vec<_> = ast
     .attrs
     .iter()
     .filter_map(|a| {
@@ -68,9 +83,10 @@ pub fn derive(input: TokenStream) -> TokenStream {
                 _ => panic!("invalid expr config,"),
               };
 
+              let expr: Expr = parse(TokenStream::from_str(&expr_str).unwrap()).unwrap();
               Some(Config {
                 ty: ty_tokens,
-                expr: parse(TokenStream::from_str(&expr_str).unwrap()).unwrap(),
+                expr: expr.into_tokens(),
               })
             }
             _ => panic!("#[has_item_key(..)] invalid argument syntax."),
@@ -82,6 +98,12 @@ pub fn derive(input: TokenStream) -> TokenStream {
     })
     .collect();
 
+  match parse_key_fields(&ast.data) {
+    Ok(Some(config)) => configs.push(config),
+    Ok(None) => {}
+    Err(error) => return error.into(),
+  }
+
   if configs.is_empty() {
     panic!("No config attribute was found.");
   }
@@ -104,3 +126,177 @@ pub fn derive(input: TokenStream) -> TokenStream {
 
   tokens.into()
 }
+
+fn parse_key_fields(data: &Data) -> Result<Option<Config>, Tokens> {
+  let fields = key_fields(data)?;
+  if fields.is_empty() {
+    return Ok(None);
+  }
+  if let Some(field) = fields.iter().find(|f| f.borrow.is_some()) {
+    return Err(error(field.span, "#[item_key(borrow = ..)] requires #[derive(HasItemKeyRef)]"));
+  }
+  let types: Vec<_> = fields.iter().map(|f| f.ty.into_tokens()).collect();
+  let exprs: Vec<_> = fields
+    .iter()
+    .map(|f| {
+      let access = &f.access;
+      quote! { #access.clone() }
+    })
+    .collect();
+  Ok(Some(Config {
+    ty: tuple(types),
+    expr: tuple(exprs),
+  }))
+}
+
+// A field marked with `#[item_key]`.
+pub(crate) struct KeyField<'a> {
+  // `self.name` or `self.0`
+  pub access: Tokens,
+  pub ty: &'a Type,
+  // field name or index, for error messages
+  pub name: String,
+  // type of `#[item_key(borrow = "..")]`
+  pub borrow: Option<Type>,
+  pub span: ::proc_macro2::Span,
+}
+
+// Returns the `#[item_key]` fields of a struct, in declaration order.
+pub(crate) fn key_fields(data: &Data) -> Result<Vec<KeyField<'_>>, Tokens> {
+  let fields: Vec<&Field> = match *data {
+    Data::Struct(DataStruct { ref fields, .. }) => fields.iter().collect(),
+    Data::Enum(DataEnum { ref variants, .. }) => {
+      for field in variants.iter().flat_map(|v| v.fields.iter()) {
+        if let Some(attr) = field.attrs.iter().find(|a| is_item_key_attr(a)) {
+          return Err(error(attr.span(), "#[item_key] is only supported on struct fields"));
+        }
+      }
+      return Ok(vec![]);
+    }
+    Data::Union(_) => return Ok(vec![]),
+  };
+
+  let mut key_fields = vec![];
+  for (i, field) in fields.into_iter().enumerate() {
+    let mut attrs = field.attrs.iter().filter(|a| is_item_key_attr(a));
+    let attr = match attrs.next() {
+      Some(attr) => attr,
+      None => continue,
+    };
+    if let Some(attr) = attrs.next() {
+      return Err(error(attr.span(), "duplicated #[item_key] attribute"));
+    }
+    let borrow = match attr.interpret_meta() {
+      Some(Meta::Word(_)) => None,
+      Some(Meta::List(ref list)) if list.nested.len() == 1 => match list.nested.iter().next() {
+        Some(&NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+          ref ident,
+          lit: Lit::Str(ref lit),
+          ..
+        }))) if ident == "borrow" => {
+          let ty = TokenStream::from_str(&lit.value())
+            .ok()
+            .and_then(|input| parse::<Type>(input).ok());
+          match ty {
+            Some(ty) => Some(ty),
+            None => return Err(error(lit.span(), "#[item_key(borrow = ..)] invalid type")),
+          }
+        }
+        _ => return Err(error(attr.span(), "#[item_key(..)] only takes `borrow = \"Type\"`")),
+      },
+      _ => return Err(error(attr.span(), "#[item_key(..)] only takes `borrow = \"Type\"`")),
+    };
+
+    let (access, name) = match field.ident {
+      Some(ref ident) => (quote! { self.#ident }, ident.to_string()),
+      None => {
+        let index = Index::from(i);
+        (quote! { self.#index }, i.to_string())
+      }
+    };
+    key_fields.push(KeyField {
+      access,
+      ty: &field.ty,
+      name,
+      borrow,
+      span: attr.span(),
+    });
+  }
+  Ok(key_fields)
+}
+
+// The only part, or a tuple of the parts.
+pub(crate) fn tuple(mut parts: Vec<Tokens>) -> Tokens {
+  if parts.len() == 1 {
+    parts.remove(0)
+  } else {
+    quote! { (#(#parts),*) }
+  }
+}
+
+pub fn derive_ref(input: TokenStream) -> TokenStream {
+  let ast: DeriveInput = parse(input).unwrap();
+  let ident = ast.ident;
+
+  let mut fields = match key_fields(&ast.data) {
+    Ok(fields) => fields,
+    Err(error) => return error.into(),
+  };
+  let field = match fields.len() {
+    0 => panic!("#[derive(HasItemKeyRef)] no #[item_key] field was found."),
+    1 => fields.remove(0),
+    _ => {
+      return error(fields[1].span, "#[derive(HasItemKeyRef)] supports only 1 #[item_key] field").into();
+    }
+  };
+
+  let access = &field.access;
+  let (ty, expr) = match field.borrow {
+    Some(ref ty) => (
+      ty.into_tokens(),
+      quote! { ::std::borrow::Borrow::<#ty>::borrow(&#access) },
+    ),
+    None => {
+      let ty = field.ty;
+      (quote! { #ty }, quote! { &#access })
+    }
+  };
+  let tokens = quote! {
+    impl ::s2_utils::list::HasItemKeyRef<#ty> for #ident {
+      fn get_item_key_ref(&self) -> &#ty {
+        #expr
+      }
+    }
+
+    impl<'a> ::s2_utils::list::HasItemKey<&'a #ty> for &'a #ident {
+      fn get_item_key(&self) -> &'a #ty {
+        let item: &'a #ident = *self;
+        ::s2_utils::list::HasItemKeyRef::<#ty>::get_item_key_ref(item)
+      }
+    }
+  };
+
+  tokens.into()
+}
+
+pub(crate) fn is_item_key_attr(attr: &Attribute) -> bool {
+  is_attr(attr, "item_key")
+}
+
+// `#[name]` or `#[name(..)]`
+pub(crate) fn is_attr(attr: &Attribute, name: &str) -> bool {
+  attr.path.segments.len() == 1
+    && attr
+      .path
+      .segments
+      .iter()
+      .next()
+      .map(|s| s.ident == name)
+      .unwrap_or(false)
+}
+
+pub(crate) fn error(span: ::proc_macro2::Span, message: &str) -> Tokens {
+  quote_spanned! {span=>
+    compile_error!(#message);
+  }
+}
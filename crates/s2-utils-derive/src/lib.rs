@@ -10,9 +10,11 @@ extern crate quote;
 
 use proc_macro::TokenStream;
 
+mod diff_fields;
 mod has_item_key;
 mod str_enum;
 mod struct_mapper;
+mod try_has_item_key;
 
 #[proc_macro_derive(StructMapper, attributes(map_from))]
 pub fn derive_struct_mapper(input: TokenStream) -> TokenStream {
@@ -24,7 +26,22 @@ pub fn derive_str_enum(input: TokenStream) -> TokenStream {
   str_enum::derive(input)
 }
 
-#[proc_macro_derive(HasItemKey, attributes(has_item_key))]
+#[proc_macro_derive(HasItemKey, attributes(has_item_key, item_key))]
 pub fn derive_has_item_key(input: TokenStream) -> TokenStream {
   has_item_key::derive(input)
 }
+
+#[proc_macro_derive(HasItemKeyRef, attributes(item_key))]
+pub fn derive_has_item_key_ref(input: TokenStream) -> TokenStream {
+  has_item_key::derive_ref(input)
+}
+
+#[proc_macro_derive(DiffFields, attributes(diff_fields))]
+pub fn derive_diff_fields(input: TokenStream) -> TokenStream {
+  diff_fields::derive(input)
+}
+
+#[proc_macro_derive(TryHasItemKey, attributes(try_has_item_key, item_key))]
+pub fn derive_try_has_item_key(input: TokenStream) -> TokenStream {
+  try_has_item_key::derive(input)
+}
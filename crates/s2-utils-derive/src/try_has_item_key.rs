@@ -0,0 +1,192 @@
+use proc_macro::TokenStream;
+
+use quote::{ToTokens, Tokens};
+use syn::*;
+
+use std::str::FromStr;
+
+use crate::has_item_key::{error, is_attr, key_fields, tuple, KeyField};
+
+/*
+Syntax
+
+// `Option` fields are unwrapped, `None` fails with `MissingItemKey`
+#[derive(TryHasItemKey)]
+struct Listing { #[item_key] sku: Option<String>, qty: i32 }
+
+#[derive(TryHasItemKey)]
+#[try_has_item_key(i64, error = "std::num::ParseIntError", expr = "self.id.parse()")]
+struct Row { id: String }
+
+*/
+
+struct Config {
+  ty: Tokens,
+  error: Tokens,
+  // evaluates to `Result<ty, error>`
+  expr: Tokens,
+}
+
+pub fn derive(input: TokenStream) -> TokenStream {
+  let ast: DeriveInput = parse(input).unwrap();
+  let ident = ast.ident;
+
+  let mut configs: Vec<_> = ast
+    .attrs
+    .iter()
+    .filter(|a| is_attr(a, "try_has_item_key"))
+    .map(parse_config)
+    .collect();
+
+  match parse_key_fields(&ast.data) {
+    Ok(Some(config)) => configs.push(config),
+    Ok(None) => {}
+    Err(error) => return error.into(),
+  }
+
+  if configs.is_empty() {
+    panic!("#[derive(TryHasItemKey)] no #[try_has_item_key(..)] or #[item_key] was found.");
+  }
+
+  let impls: Vec<_> = configs
+    .into_iter()
+    .map(|Config { ty, error, expr }| {
+      quote! {
+        impl ::s2_utils::list::TryHasItemKey<#ty> for #ident {
+          type Error = #error;
+
+          fn try_get_item_key(&self) -> ::std::result::Result<#ty, #error> {
+            #expr
+          }
+        }
+      }
+    })
+    .collect();
+  let tokens = quote! {
+    #(#impls)*
+  };
+
+  tokens.into()
+}
+
+fn parse_config(attr: &Attribute) -> Config {
+  let list = match attr.interpret_meta() {
+    Some(Meta::List(list)) => list,
+    _ => panic!("#[try_has_item_key(..)] invalid argument syntax."),
+  };
+  let args: Vec<_> = list.nested.iter().collect();
+  if args.len() != 3 {
+    panic!("#[try_has_item_key(..)] 3 arguments expected.")
+  }
+
+  let ty = match *args[0] {
+    NestedMeta::Meta(Meta::Word(ref ident)) => ident.into_tokens(),
+    NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+      ref ident,
+      lit: Lit::Str(ref lit),
+      ..
+    }))
+      if ident == "Key" =>
+    {
+      parse_str::<Type>(&lit.value()).into_tokens()
+    }
+    _ => panic!("#[try_has_item_key(..)] invalid type config."),
+  };
+
+  let error = match *args[1] {
+    NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+      ref ident,
+      lit: Lit::Str(ref lit),
+      ..
+    }))
+      if ident == "error" =>
+    {
+      parse_str::<Type>(&lit.value()).into_tokens()
+    }
+    _ => panic!("#[try_has_item_key(..)] invalid error config."),
+  };
+
+  let expr = match *args[2] {
+    NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+      ref ident,
+      lit: Lit::Str(ref lit),
+      ..
+    }))
+      if ident == "expr" =>
+    {
+      parse_str::<Expr>(&lit.value()).into_tokens()
+    }
+    NestedMeta::Literal(Lit::Str(ref lit)) => parse_str::<Expr>(&lit.value()).into_tokens(),
+    _ => panic!("#[try_has_item_key(..)] invalid expr config."),
+  };
+
+  Config { ty, error, expr }
+}
+
+fn parse_str<T: synom::Synom>(s: &str) -> T {
+  parse(TokenStream::from_str(s).unwrap()).unwrap()
+}
+
+fn parse_key_fields(data: &Data) -> Result<Option<Config>, Tokens> {
+  let fields = key_fields(data)?;
+  if fields.is_empty() {
+    return Ok(None);
+  }
+  if let Some(field) = fields.iter().find(|f| f.borrow.is_some()) {
+    return Err(error(field.span, "#[item_key(borrow = ..)] requires #[derive(HasItemKeyRef)]"));
+  }
+
+  let mut types = vec![];
+  let mut exprs = vec![];
+  for KeyField {
+    access, ty, name, ..
+  } in fields
+  {
+    match option_inner_type(ty) {
+      Some(ty) => {
+        types.push(quote! { #ty });
+        exprs.push(quote! {
+          match #access {
+            Some(ref v) => v.clone(),
+            None => return Err(::s2_utils::list::MissingItemKey { field: #name }),
+          }
+        });
+      }
+      None => {
+        types.push(quote! { #ty });
+        exprs.push(quote! { #access.clone() });
+      }
+    }
+  }
+
+  let expr = tuple(exprs);
+  Ok(Some(Config {
+    ty: tuple(types),
+    error: quote! { ::s2_utils::list::MissingItemKey },
+    expr: quote! { Ok(#expr) },
+  }))
+}
+
+// `T` of an `Option<T>` field type
+fn option_inner_type(ty: &Type) -> Option<&Type> {
+  let path = match *ty {
+    Type::Path(TypePath {
+      qself: None,
+      ref path,
+    }) => path,
+    _ => return None,
+  };
+  let segment = path.segments.iter().last()?;
+  if segment.ident != "Option" {
+    return None;
+  }
+  match segment.arguments {
+    PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => {
+      match *args.args.iter().next()? {
+        GenericArgument::Type(ref ty) => Some(ty),
+        _ => None,
+      }
+    }
+    _ => None,
+  }
+}
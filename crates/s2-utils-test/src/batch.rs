@@ -0,0 +1,119 @@
+use s2_utils::list::{
+  get_changed_items, Batch, BatchFailure, BatchPlanner, ChangeKind, Checkpoint,
+};
+
+use crate::support::{item, Item};
+
+fn items(ids: &[i32], name: &str) -> Vec<Item> {
+  ids.iter().map(|&id| item(id, name)).collect()
+}
+
+fn ids(batch: &Batch<Item, Item>) -> Vec<i32> {
+  match batch {
+    Batch::Insert(items) => items.iter().map(|i| i.id).collect(),
+    Batch::Update(items) => items.iter().map(|(_, i)| i.id).collect(),
+    Batch::Delete(items) => items.iter().map(|i| i.id).collect(),
+  }
+}
+
+#[test]
+fn test_batch_plan() {
+  let existing = items(&[1, 2, 3, 4, 5], "old");
+  let new = items(&[4, 5, 6, 7, 8, 9, 10], "new");
+  let changes = get_changed_items(&existing, &new);
+
+  let plan = BatchPlanner::new(2)
+    .order([ChangeKind::Delete, ChangeKind::Insert, ChangeKind::Update])
+    .plan(&changes);
+  let batches: Vec<_> = plan.batches.iter().map(|b| (b.kind(), ids(b))).collect();
+  assert_eq!(
+    batches,
+    vec![
+      (ChangeKind::Delete, vec![1, 2]),
+      (ChangeKind::Delete, vec![3]),
+      (ChangeKind::Insert, vec![6, 7]),
+      (ChangeKind::Insert, vec![8, 9]),
+      (ChangeKind::Insert, vec![10]),
+      (ChangeKind::Update, vec![4, 5]),
+    ]
+  );
+
+  let empty = get_changed_items::<i32, Item, Item>(&[], &[]);
+  assert!(BatchPlanner::new(2).plan(&empty).is_empty());
+}
+
+#[test]
+fn test_batch_plan_weight() {
+  let existing = vec![];
+  let new = vec![
+    item(1, "aaaa"),
+    item(2, "aaaa"),
+    item(3, "aaaaaaaaaaaa"),
+    item(4, "a"),
+    item(5, "a"),
+  ];
+  let changes = get_changed_items(&existing, &new);
+  let plan = BatchPlanner::new(3)
+    .max_weight(10, |i: &Item| i.name.len(), |i: &Item| i.name.len())
+    .plan(&changes);
+  let batches: Vec<_> = plan.batches.iter().map(ids).collect();
+  // the heavy item gets a batch of its own
+  assert_eq!(batches, vec![vec![1, 2], vec![3], vec![4, 5]]);
+}
+
+#[test]
+fn test_batch_plan_run() {
+  let existing = vec![];
+  let new = items(&[1, 2, 3, 4, 5], "new");
+  let changes = get_changed_items(&existing, &new);
+  let plan = BatchPlanner::new(2).plan(&changes);
+  assert_eq!(plan.len(), 3);
+
+  // batch 1 fails twice then succeeds, within 3 attempts
+  let mut calls = vec![];
+  let report = plan.run(Checkpoint::default(), 3, |batch| {
+    calls.push(ids(batch));
+    if ids(batch) == vec![3, 4] && calls.len() < 4 {
+      Err("timeout")
+    } else {
+      Ok(())
+    }
+  });
+  assert!(report.is_ok());
+  assert_eq!(report.checkpoint, Checkpoint { next_batch: 3 });
+  assert_eq!(calls.len(), 5);
+  assert!(plan.is_done(report.checkpoint));
+
+  // batch 1 always fails, resume from the checkpoint
+  let report = plan.run(Checkpoint::default(), 2, |batch| {
+    if ids(batch) == vec![3, 4] {
+      Err("down")
+    } else {
+      Ok(())
+    }
+  });
+  assert_eq!(
+    report.failure,
+    Some(BatchFailure {
+      batch: 1,
+      kind: ChangeKind::Insert,
+      errors: vec!["down", "down"],
+    })
+  );
+  assert_eq!(report.checkpoint, Checkpoint { next_batch: 1 });
+
+  let mut resumed = vec![];
+  let report = plan.run::<(), _>(report.checkpoint, 1, |batch| {
+    resumed.push(ids(batch));
+    Ok(())
+  });
+  assert!(report.is_ok());
+  assert_eq!(resumed, vec![vec![3, 4], vec![5]]);
+  assert_eq!(
+    plan
+      .iter_from(Checkpoint { next_batch: 2 })
+      .map(|(i, _)| i)
+      .collect::<Vec<_>>(),
+    vec![2]
+  );
+}
@@ -0,0 +1,84 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use s2_utils::list::{
+  get_changed_btree_map_items, get_changed_hash_map_items, get_changed_items,
+  get_changed_items_iter, get_dup_items, get_dup_items_iter, DiffByKey,
+};
+
+use crate::support::{item, Item};
+
+#[test]
+fn test_slice_inputs() {
+  let l1 = [item(1, "a"), item(2, "a"), item(2, "b")];
+  let l2 = [item(0, "x"), item(2, "c"), item(3, "c")];
+
+  // arrays and sub slices, no `Vec` needed
+  let changes = get_changed_items(&l1, &l2[1..]);
+  assert_eq!(changes.add, vec![&l2[2]]);
+  assert_eq!(changes.update, vec![(&l1[1], &l2[1])]);
+  assert_eq!(changes.delete, vec![&l1[2], &l1[0]]);
+
+  let dups: Vec<(i32, _)> = get_dup_items(&l1[1..]);
+  assert_eq!(dups, vec![(2, vec![(0, &l1[1]), (1, &l1[2])])]);
+}
+
+#[test]
+fn test_get_changed_items_iter() {
+  let l1 = vec![item(1, "a"), item(2, "a"), item(1, "b")];
+  let l2 = vec![item(2, "b"), item(3, "b")];
+  let expected = get_changed_items(&l1, &l2);
+
+  let existing: VecDeque<_> = l1.iter().cloned().collect();
+  let new: BTreeMap<_, _> = l2.iter().map(|i| (i.id, i.clone())).collect();
+  let changes = get_changed_items_iter(&existing, new.values());
+  assert_eq!(changes.add, expected.add);
+  assert_eq!(changes.update, expected.update);
+  assert_eq!(changes.delete, expected.delete);
+
+  let dups = get_dup_items_iter(&existing);
+  let expected: Vec<(i32, _)> = get_dup_items(&l1);
+  assert_eq!(dups, expected);
+
+  // indexes are positions in the filtered iteration
+  let dups: Vec<(i32, _)> = get_dup_items_iter(existing.iter().filter(|i| i.id != 2));
+  assert_eq!(dups, vec![(1, vec![(0, &l1[0]), (1, &l1[2])])]);
+}
+
+#[test]
+fn test_map_diffs() {
+  let existing: BTreeMap<_, _> = vec![(1, "a"), (2, "b"), (3, "c")].into_iter().collect();
+  let new: BTreeMap<_, _> = vec![(4, "d"), (2, "x"), (3, "c")].into_iter().collect();
+  let changes = get_changed_btree_map_items(&existing, &new);
+  assert_eq!(changes.add, vec![&"d"]);
+  assert_eq!(changes.update, vec![(&"b", &"x"), (&"c", &"c")]);
+  assert_eq!(changes.delete, vec![&"a"]);
+
+  let existing: HashMap<_, _> = existing.into_iter().collect();
+  let new: HashMap<_, _> = new.into_iter().collect();
+  let changes = get_changed_hash_map_items(&existing, &new);
+  let mut update = changes.update.clone();
+  update.sort();
+  assert_eq!(changes.add, vec![&"d"]);
+  assert_eq!(update, vec![(&"b", &"x"), (&"c", &"c")]);
+  assert_eq!(changes.delete, vec![&"a"]);
+}
+
+#[test]
+fn test_diff_by_key() {
+  let l1: Vec<Item> = vec![item(1, "a"), item(2, "a")];
+  let l2 = vec![item(2, "b"), item(3, "b")];
+
+  let changes = l1.diff_by_key(&l2);
+  assert_eq!(changes.add, vec![&l2[1]]);
+  assert_eq!(changes.update, vec![(&l1[1], &l2[0])]);
+  assert_eq!(changes.delete, vec![&l1[0]]);
+
+  let changes = l1[..1].diff_by_key(&l2[..1]);
+  assert_eq!(changes.add.len(), 1);
+  assert_eq!(changes.delete.len(), 1);
+
+  let m1: HashMap<_, _> = l1.iter().map(|i| (i.id, i)).collect();
+  let m2: HashMap<_, _> = l2.iter().map(|i| (i.id, i)).collect();
+  let changes = m1.diff_by_key(&m2);
+  assert_eq!(changes.update, vec![(&&l1[1], &&l2[0])]);
+}
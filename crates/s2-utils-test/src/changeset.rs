@@ -0,0 +1,208 @@
+use s2_utils::list::{
+  get_changed_items, ChangeKind, ChangeSet, ComposeError, KeyedItem, KeyedUpdate,
+};
+
+use crate::support::{item, Item, Lcg};
+
+#[test]
+fn test_change_set_from_changed_items() {
+  let l1 = vec![item(1, "a"), item(2, "b")];
+  let l2 = vec![item(2, "c"), item(3, "d")];
+
+  let changes: ChangeSet<i32, Item, Item> = get_changed_items(&l1, &l2).into();
+  assert_eq!(
+    changes,
+    ChangeSet {
+      add: vec![KeyedItem {
+        key: 3,
+        item: item(3, "d"),
+      }],
+      update: vec![KeyedUpdate {
+        key: 2,
+        existing: item(2, "b"),
+        new: item(2, "c"),
+      }],
+      delete: vec![KeyedItem {
+        key: 1,
+        item: item(1, "a"),
+      }],
+    }
+  );
+
+  let replay = changes.as_changed_items();
+  assert_eq!(replay.add, vec![&l2[1]]);
+  assert_eq!(replay.update, vec![(&l1[1], &l2[0])]);
+  assert_eq!(replay.delete, vec![&l1[0]]);
+}
+
+#[test]
+fn test_change_set_serde() {
+  let l1 = vec![item(1, "a"), item(2, "b")];
+  let l2 = vec![item(2, "c"), item(3, "d")];
+  let changes: ChangeSet<i32, Item, Item> = get_changed_items(&l1, &l2).into();
+
+  let json = serde_json::to_value(&changes).unwrap();
+  assert_eq!(
+    json,
+    serde_json::json!({
+      "add": [{ "key": 3, "item": { "id": 3, "name": "d" } }],
+      "update": [{
+        "key": 2,
+        "existing": { "id": 2, "name": "b" },
+        "new": { "id": 2, "name": "c" }
+      }],
+      "delete": [{ "key": 1, "item": { "id": 1, "name": "a" } }]
+    })
+  );
+
+  let decoded: ChangeSet<i32, Item, Item> = serde_json::from_value(json).unwrap();
+  assert_eq!(decoded, changes);
+}
+
+#[test]
+fn test_change_set_from_repeated_deletes() {
+  // the extra existing item is deleted once per new item with the key
+  let l1 = vec![item(1, "a"), item(1, "b")];
+  let l2 = vec![item(1, "c"), item(1, "d")];
+  let changes = get_changed_items(&l1, &l2);
+  assert_eq!(changes.delete, vec![&l1[1], &l1[1]]);
+
+  let changes: ChangeSet<i32, Item, Item> = changes.into();
+  assert_eq!(
+    changes.delete,
+    vec![KeyedItem {
+      key: 1,
+      item: item(1, "b"),
+    }]
+  );
+}
+
+// deterministic pseudo random list, some keys have several items
+fn random_list(rng: &mut Lcg) -> Vec<Item> {
+  let names = ["a", "b", "c"];
+  let mut items = vec![];
+  for id in 0..10 {
+    for _ in 0..rng.below(3) {
+      items.push(item(id, names[rng.below(names.len())]));
+    }
+  }
+  items
+}
+
+fn diff(l1: &[Item], l2: &[Item]) -> ChangeSet<i32, Item, Item> {
+  get_changed_items(l1, l2).into()
+}
+
+fn applied(changes: &ChangeSet<i32, Item, Item>, items: &[Item]) -> Vec<Item> {
+  let mut items = items.to_vec();
+  changes.apply(&mut items);
+  sorted(items)
+}
+
+fn sorted(mut items: Vec<Item>) -> Vec<Item> {
+  items.sort_by(|a, b| (a.id, &a.name).cmp(&(b.id, &b.name)));
+  items
+}
+
+#[test]
+fn test_change_set_compose_property() {
+  let mut rng = Lcg::new(1);
+  for _ in 0..500 {
+    let l0 = random_list(&mut rng);
+    let a = diff(&l0, &random_list(&mut rng));
+    // `get_changed_items` drops all but the last new item of a matched key,
+    // so applying `a` may not give the random list, diff `b` from its result
+    let l1 = applied(&a, &l0);
+    let b = diff(&l1, &random_list(&mut rng));
+
+    let sequential = applied(&b, &l1);
+    let composed = a.clone().compose(b.clone()).unwrap();
+    assert_eq!(applied(&composed, &l0), sequential);
+
+    // inverting reverts the changes
+    assert_eq!(
+      applied(&composed.clone().invert(), &sequential),
+      sorted(l0.clone())
+    );
+    assert_eq!(applied(&a.clone().compact(), &l0), applied(&a, &l0));
+  }
+}
+
+#[test]
+fn test_change_set_compose() {
+  let l0 = vec![item(1, "a"), item(2, "a")];
+  let l1 = vec![item(1, "b"), item(3, "a")];
+  let l2 = vec![item(1, "b"), item(2, "b")];
+
+  let composed = diff(&l0, &l1).compose(diff(&l1, &l2)).unwrap();
+  assert_eq!(
+    composed.update,
+    vec![
+      KeyedUpdate {
+        key: 1,
+        existing: item(1, "a"),
+        new: item(1, "b"),
+      },
+      // deleted then added again
+      KeyedUpdate {
+        key: 2,
+        existing: item(2, "a"),
+        new: item(2, "b"),
+      },
+    ]
+  );
+  // added then deleted
+  assert!(composed.add.is_empty());
+  assert!(composed.delete.is_empty());
+
+  let compact = composed.clone().compact();
+  assert_eq!(compact.update.len(), 2);
+  let unchanged = diff(&l2, &l2).compact();
+  assert!(unchanged.is_empty());
+
+  let inverted = composed.invert();
+  assert_eq!(inverted.update[0].new, item(1, "a"));
+
+  // deletes item 2 twice
+  let err = diff(&l0, &l1).compose(diff(&l0, &l1)).unwrap_err();
+  assert_eq!(
+    err,
+    ComposeError {
+      key: 2,
+      first: ChangeKind::Delete,
+      second: ChangeKind::Delete,
+    }
+  );
+  assert_eq!(err.to_string(), "Delete of key 2 can't follow Delete");
+}
+
+#[test]
+fn test_change_set_duplicate_keys() {
+  let l0 = vec![item(2, "x"), item(2, "y")];
+  let l1 = vec![item(2, "z")];
+  let l2 = vec![item(2, "w")];
+
+  // an update and a delete of the same key
+  let a = diff(&l0, &l1);
+  assert_eq!((a.update.len(), a.delete.len()), (1, 1));
+  assert_eq!(applied(&a, &l0), l1);
+
+  let composed = a.compose(diff(&l1, &l2)).unwrap();
+  assert_eq!(applied(&composed, &l0), l2);
+  assert_eq!(
+    composed.update,
+    vec![KeyedUpdate {
+      key: 2,
+      existing: item(2, "x"),
+      new: item(2, "w"),
+    }]
+  );
+  assert_eq!(composed.delete.len(), 1);
+  assert_eq!(applied(&composed.invert(), &l2), l0);
+
+  // a delete followed by an insert of the same key
+  let l2 = vec![item(2, "z"), item(3, "a")];
+  let l3 = vec![item(2, "z"), item(2, "v")];
+  let composed = diff(&l1, &l2).compose(diff(&l2, &l3)).unwrap();
+  assert_eq!(applied(&composed, &l1), applied(&diff(&l1, &l3), &l1));
+}
@@ -0,0 +1,60 @@
+use s2_utils::list::{
+  dedup_map, dedup_map_stable, dedup_map_stable_by_eq, dedup_map_stable_counts,
+  dedup_map_stable_counts_by_eq,
+};
+
+struct Order {
+  skus: Vec<&'static str>,
+  coupon: Option<&'static str>,
+  warehouse: &'static str,
+}
+
+fn orders() -> Vec<Order> {
+  vec![
+    Order {
+      skus: vec!["c", "a"],
+      coupon: None,
+      warehouse: "us",
+    },
+    Order {
+      skus: vec!["b", "c"],
+      coupon: Some("x"),
+      warehouse: "ca",
+    },
+    Order {
+      skus: vec!["a"],
+      coupon: Some("x"),
+      warehouse: "us",
+    },
+  ]
+}
+
+#[test]
+fn test_dedup_map_stable() {
+  let orders = orders();
+  let sorted: Vec<&str> = dedup_map(&orders, |o| o.skus.clone());
+  assert_eq!(sorted, vec!["a", "b", "c"]);
+  let skus: Vec<&str> = dedup_map_stable(&orders, |o| o.skus.clone());
+  assert_eq!(skus, vec!["c", "a", "b"]);
+  let coupons: Vec<&str> = dedup_map_stable(&orders, |o| o.coupon);
+  assert_eq!(coupons, vec!["x"]);
+  let warehouses: Vec<&str> = dedup_map_stable(&orders, |o| o.warehouse);
+  assert_eq!(warehouses, vec!["us", "ca"]);
+  let counts: Vec<(&str, usize)> = dedup_map_stable_counts(&orders, |o| o.skus.clone());
+  assert_eq!(counts, vec![("c", 2), ("a", 2), ("b", 1)]);
+}
+
+#[test]
+fn test_dedup_map_stable_by_eq() {
+  let orders = orders();
+  // f64 is neither `Ord` nor `Hash`
+  let weights: Vec<f64> = dedup_map_stable_by_eq(&orders, |o| {
+    o.skus
+      .iter()
+      .map(|s| s.len() as f64 * 0.5)
+      .collect::<Vec<_>>()
+  });
+  assert_eq!(weights, vec![0.5]);
+  let counts: Vec<(&str, usize)> = dedup_map_stable_counts_by_eq(&orders, |o| o.coupon);
+  assert_eq!(counts, vec![("x", 2)]);
+}
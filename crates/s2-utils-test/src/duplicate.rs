@@ -0,0 +1,143 @@
+use s2_utils::list::{
+  get_changed_items, get_changed_items_with_policy, DuplicateKeyError, DuplicatePolicy, ListSide,
+  Resolved,
+};
+use s2_utils_derive::HasItemKey;
+
+use crate::support::dedup_deletes;
+
+#[derive(Debug, Clone, HasItemKey, PartialEq)]
+#[has_item_key(i32, expr = "self.0")]
+struct A(i32, &'static str);
+
+#[test]
+fn test_default_policies() {
+  let l1 = vec![A(1, "a"), A(2, "b"), A(2, "c"), A(3, "d")];
+  let l2 = vec![A(2, "x"), A(4, "y"), A(2, "z"), A(4, "w")];
+
+  let changes = get_changed_items_with_policy(
+    &l1,
+    &l2,
+    &DuplicatePolicy::KeepFirst,
+    &DuplicatePolicy::KeepLast,
+  )
+  .unwrap();
+  assert_eq!(changes.add, vec![Resolved::Borrowed(&l2[3])]);
+  assert_eq!(
+    changes.update,
+    vec![(Resolved::Borrowed(&l1[1]), Resolved::Borrowed(&l2[2]))]
+  );
+  assert_eq!(changes.delete, vec![&l1[2], &l1[0], &l1[3]]);
+}
+
+#[test]
+fn test_default_policies_match_get_changed_items() {
+  let lists = vec![
+    vec![],
+    vec![A(1, "a"), A(2, "b"), A(2, "c"), A(3, "d")],
+    vec![A(2, "x"), A(2, "y"), A(2, "z")],
+    vec![A(4, "a"), A(1, "b"), A(4, "c"), A(1, "d"), A(5, "e")],
+    vec![A(5, "x"), A(5, "y"), A(3, "z")],
+  ];
+  for l1 in &lists {
+    for l2 in &lists {
+      let mut expected = get_changed_items(l1, l2);
+      dedup_deletes(&mut expected.delete);
+      let changes = get_changed_items_with_policy(
+        l1,
+        l2,
+        &DuplicatePolicy::KeepFirst,
+        &DuplicatePolicy::KeepLast,
+      )
+      .unwrap();
+
+      let update: Vec<_> = changes
+        .update
+        .iter()
+        .map(|(ei, ni)| (&**ei, &**ni))
+        .collect();
+      assert_eq!(update, expected.update);
+      assert_eq!(changes.delete, expected.delete);
+
+      // unmatched new items sharing a key: only the last one is added, in
+      // the position of the first one
+      let mut expected_add: Vec<&A> = vec![];
+      for ni in expected.add {
+        match expected_add.iter().position(|a| a.0 == ni.0) {
+          Some(pos) => expected_add[pos] = ni,
+          None => expected_add.push(ni),
+        }
+      }
+      let add: Vec<_> = changes.add.iter().map(|ni| &**ni).collect();
+      assert_eq!(add, expected_add);
+    }
+  }
+}
+
+#[test]
+fn test_keep_last_and_merge() {
+  let l1 = vec![A(1, "a"), A(1, "b"), A(1, "c")];
+  let l2 = vec![A(1, "x"), A(1, "y")];
+
+  let changes = get_changed_items_with_policy(
+    &l1,
+    &l2,
+    &DuplicatePolicy::KeepLast,
+    &DuplicatePolicy::merge(|a: &A, b: &A| A(a.0, if a.1 < b.1 { b.1 } else { a.1 })),
+  )
+  .unwrap();
+  assert!(changes.add.is_empty());
+  assert_eq!(
+    changes.update,
+    vec![(Resolved::Borrowed(&l1[2]), Resolved::Merged(A(1, "y")))]
+  );
+  assert_eq!(changes.delete, vec![&l1[0], &l1[1]]);
+
+  let changes = get_changed_items_with_policy(
+    &l1,
+    &l2,
+    &DuplicatePolicy::merge(|a: &A, _: &A| a.clone()),
+    &DuplicatePolicy::KeepFirst,
+  )
+  .unwrap();
+  assert_eq!(changes.update[0].0 .1, "a");
+  assert_eq!(*changes.update[0].1, A(1, "x"));
+  assert_eq!(changes.delete, vec![&l1[1], &l1[2]]);
+}
+
+#[test]
+fn test_error_policy() {
+  let l1 = vec![A(1, "a"), A(2, "b"), A(1, "c"), A(2, "d"), A(3, "e")];
+  let l2 = vec![A(1, "a"), A(3, "b"), A(3, "c")];
+
+  let err = get_changed_items_with_policy(
+    &l1,
+    &l2,
+    &DuplicatePolicy::Error,
+    &DuplicatePolicy::KeepLast,
+  )
+  .err()
+  .unwrap();
+  assert_eq!(
+    err,
+    DuplicateKeyError {
+      side: ListSide::Existing,
+      keys: vec![(1, vec![0, 2]), (2, vec![1, 3])],
+    }
+  );
+  assert_eq!(
+    err.to_string(),
+    "duplicated keys in Existing items: 1 at [0, 2] 2 at [1, 3]"
+  );
+
+  let err = get_changed_items_with_policy(
+    &l1[..2],
+    &l2,
+    &DuplicatePolicy::Error,
+    &DuplicatePolicy::Error,
+  )
+  .err()
+  .unwrap();
+  assert_eq!(err.side, ListSide::New);
+  assert_eq!(err.keys, vec![(3, vec![1, 2])]);
+}
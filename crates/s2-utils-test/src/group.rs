@@ -0,0 +1,236 @@
+use s2_utils::GroupByKey;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Stock {
+  warehouse: &'static str,
+  sku: &'static str,
+  qty: i32,
+}
+
+fn stocks() -> Vec<Stock> {
+  let stock = |warehouse, sku, qty| Stock {
+    warehouse,
+    sku,
+    qty,
+  };
+  vec![
+    stock("us", "a", 3),
+    stock("ca", "a", 1),
+    stock("us", "b", 5),
+    stock("uk", "c", 2),
+    stock("ca", "b", 4),
+    stock("us", "c", 3),
+  ]
+}
+
+#[test]
+fn test_group_by_key() {
+  let groups: Vec<_> = stocks()
+    .group_by_key(|s| s.warehouse)
+    .map(|(w, items)| (w, items.iter().map(|s| s.sku).collect::<Vec<_>>()))
+    .collect();
+  assert_eq!(
+    groups,
+    vec![
+      ("us", vec!["a", "b", "c"]),
+      ("ca", vec!["a", "b"]),
+      ("uk", vec!["c"]),
+    ]
+  );
+}
+
+#[test]
+fn test_group_by_key_maps() {
+  let map = stocks().group_by_key_map(|s| s.sku);
+  assert_eq!(map.len(), 3);
+  assert_eq!(map["b"].len(), 2);
+
+  let sorted = stocks().group_by_key_sorted(|s| s.warehouse);
+  assert_eq!(
+    sorted.keys().cloned().collect::<Vec<_>>(),
+    vec!["ca", "uk", "us"]
+  );
+}
+
+#[test]
+fn test_aggregate_by_key() {
+  assert_eq!(
+    stocks().count_by_key(|s| s.warehouse).collect::<Vec<_>>(),
+    vec![("us", 3), ("ca", 2), ("uk", 1)]
+  );
+  assert_eq!(
+    stocks()
+      .sum_by_key(|s| s.sku, |s| s.qty)
+      .collect::<Vec<_>>(),
+    vec![("a", 4), ("b", 9), ("c", 5)]
+  );
+  assert_eq!(
+    stocks()
+      .fold_by_key(
+        |s| s.sku,
+        String::new,
+        |mut w, s| {
+          w.push_str(s.warehouse);
+          w
+        }
+      )
+      .collect::<Vec<_>>(),
+    vec![
+      ("a", "usca".to_string()),
+      ("b", "usca".to_string()),
+      ("c", "ukus".to_string())
+    ]
+  );
+
+  let min: Vec<_> = stocks()
+    .min_per_key_by(|s| s.warehouse, |a, b| a.qty.cmp(&b.qty))
+    .map(|(w, s)| (w, s.sku))
+    .collect();
+  assert_eq!(min, vec![("us", "a"), ("ca", "a"), ("uk", "c")]);
+
+  // ties return the last maximum item
+  let max: Vec<_> = vec![(1, "a"), (1, "b"), (2, "c")]
+    .max_per_key_by(|i| i.0, |_, _| std::cmp::Ordering::Equal)
+    .collect();
+  assert_eq!(max, vec![(1, (1, "b")), (2, (2, "c"))]);
+}
+
+#[test]
+fn test_group_runs_by_key() {
+  use s2_utils::GroupRunsByKey;
+
+  let lines = vec![(1, "a"), (1, "b"), (2, "c"), (3, "d"), (3, "e")];
+  let mut runs = lines.into_iter().group_runs_by_key(|l| l.0);
+  assert_eq!(runs.next(), Some((1, vec![(1, "a"), (1, "b")])));
+  assert_eq!(runs.next(), Some((2, vec![(2, "c")])));
+  assert_eq!(runs.next(), Some((3, vec![(3, "d"), (3, "e")])));
+  assert_eq!(runs.next(), None);
+
+  assert_eq!(
+    Vec::<i32>::new()
+      .into_iter()
+      .group_runs_by_key(|&i| i)
+      .count(),
+    0
+  );
+}
+
+#[test]
+fn test_group_runs_check() {
+  use s2_utils::{GroupRunsByKey, RepeatedKeyError};
+
+  let runs: Vec<_> = vec![1, 1, 2, 1, 3]
+    .into_iter()
+    .group_runs_by_key(|&i| i)
+    .check_runs()
+    .collect();
+  assert_eq!(
+    runs,
+    vec![
+      Ok((1, vec![1, 1])),
+      Ok((2, vec![2])),
+      Err(RepeatedKeyError { key: 1, index: 3 }),
+    ]
+  );
+
+  // grouped but not sorted
+  let runs: Vec<_> = vec![3, 3, 1, 2]
+    .into_iter()
+    .group_runs_by_key(|&i| i)
+    .check_runs()
+    .collect();
+  assert_eq!(
+    runs,
+    vec![Ok((3, vec![3, 3])), Ok((1, vec![1])), Ok((2, vec![2]))]
+  );
+}
+
+#[test]
+fn test_group_runs_check_sorted() {
+  use s2_utils::{GroupRunsByKey, UnsortedRunError};
+
+  let runs: Vec<_> = vec![1, 1, 3, 2, 3]
+    .into_iter()
+    .group_runs_by_key(|&i| i)
+    .check_sorted_runs()
+    .collect();
+  assert_eq!(
+    runs,
+    vec![
+      Ok((1, vec![1, 1])),
+      Ok((3, vec![3])),
+      Err(UnsortedRunError {
+        key: 2,
+        previous_key: 3,
+        index: 3
+      }),
+    ]
+  );
+
+  let runs: Vec<_> = vec![1, 3, 3, 5]
+    .into_iter()
+    .group_runs_by_key(|&i| i)
+    .check_sorted_runs()
+    .collect();
+  assert_eq!(
+    runs,
+    vec![Ok((1, vec![1])), Ok((3, vec![3, 3])), Ok((5, vec![5]))]
+  );
+}
+
+#[test]
+fn test_group_by_keys() {
+  let groups = stocks().group_by_keys((
+    |s: &Stock| s.warehouse,
+    |s: &Stock| s.qty > 2,
+    |s: &Stock| s.sku,
+  ));
+  let keys: Vec<_> = groups
+    .iter()
+    .map(|(warehouse, groups)| {
+      let groups: Vec<_> = groups
+        .iter()
+        .map(|(large, groups)| {
+          let skus: Vec<_> = groups
+            .iter()
+            .map(|(sku, items)| (*sku, items.len()))
+            .collect();
+          (*large, skus)
+        })
+        .collect();
+      (*warehouse, groups)
+    })
+    .collect();
+  assert_eq!(
+    keys,
+    vec![
+      ("us", vec![(true, vec![("a", 1), ("b", 1), ("c", 1)])]),
+      ("ca", vec![(false, vec![("a", 1)]), (true, vec![("b", 1)])]),
+      ("uk", vec![(false, vec![("c", 1)])]),
+    ]
+  );
+
+  let groups = vec![1, 2, 3].group_by_keys((|i: &i32| i % 2,));
+  assert_eq!(groups, vec![(1, vec![1, 3]), (0, vec![2])]);
+}
+
+#[test]
+fn test_pivot() {
+  let table = stocks().pivot(
+    |s| s.warehouse,
+    |s| s.sku,
+    |items| items.iter().map(|s| s.qty).sum::<i32>(),
+  );
+  assert_eq!(table.rows, vec!["us", "ca", "uk"]);
+  assert_eq!(table.columns, vec!["a", "b", "c"]);
+  assert_eq!(
+    table.cells,
+    vec![
+      vec![Some(3), Some(5), Some(3)],
+      vec![Some(1), Some(4), None],
+      vec![None, None, Some(2)],
+    ]
+  );
+  assert_eq!(table.get(&"ca", &"b"), Some(&4));
+  assert_eq!(table.get(&"uk", &"a"), None);
+}
@@ -0,0 +1,156 @@
+use s2_utils::list::{full_join, group_join, inner_join, left_join, right_join};
+use s2_utils_derive::HasItemKey;
+
+#[derive(Debug, HasItemKey, PartialEq)]
+#[has_item_key(i32, expr = "self.order_id")]
+struct Order {
+  order_id: i32,
+}
+
+#[derive(Debug, HasItemKey, PartialEq)]
+#[has_item_key(i32, expr = "self.order_id")]
+struct Shipment {
+  order_id: i32,
+  tracking: &'static str,
+}
+
+fn fixtures() -> (Vec<Order>, Vec<Shipment>) {
+  let orders = vec![
+    Order { order_id: 1 },
+    Order { order_id: 2 },
+    Order { order_id: 3 },
+  ];
+  let shipments = vec![
+    Shipment {
+      order_id: 3,
+      tracking: "a",
+    },
+    Shipment {
+      order_id: 1,
+      tracking: "b",
+    },
+    Shipment {
+      order_id: 4,
+      tracking: "c",
+    },
+    Shipment {
+      order_id: 3,
+      tracking: "d",
+    },
+  ];
+  (orders, shipments)
+}
+
+fn ids<L, R>(
+  pairs: impl Iterator<Item = (Option<L>, Option<R>)>,
+  l: impl Fn(L) -> i32,
+  r: impl Fn(R) -> &'static str,
+) -> Vec<(Option<i32>, Option<&'static str>)> {
+  pairs.map(|(a, b)| (a.map(&l), b.map(&r))).collect()
+}
+
+#[test]
+fn test_joins() {
+  let (orders, shipments) = fixtures();
+  let order_id = |o: &Order| o.order_id;
+  let tracking = |s: &Shipment| s.tracking;
+
+  assert_eq!(
+    ids(
+      inner_join(&orders, &shipments).map(|(o, s)| (Some(o), Some(s))),
+      order_id,
+      tracking
+    ),
+    vec![
+      (Some(1), Some("b")),
+      (Some(3), Some("a")),
+      (Some(3), Some("d"))
+    ]
+  );
+  assert_eq!(
+    ids(
+      left_join(&orders, &shipments).map(|(o, s)| (Some(o), s)),
+      order_id,
+      tracking
+    ),
+    vec![
+      (Some(1), Some("b")),
+      (Some(2), None),
+      (Some(3), Some("a")),
+      (Some(3), Some("d"))
+    ]
+  );
+  assert_eq!(
+    ids(
+      right_join(&orders, &shipments).map(|(o, s)| (o, Some(s))),
+      order_id,
+      tracking
+    ),
+    vec![
+      (Some(3), Some("a")),
+      (Some(1), Some("b")),
+      (None, Some("c")),
+      (Some(3), Some("d"))
+    ]
+  );
+  assert_eq!(
+    ids(full_join(&orders, &shipments), order_id, tracking),
+    vec![
+      (Some(1), Some("b")),
+      (Some(2), None),
+      (Some(3), Some("a")),
+      (Some(3), Some("d")),
+      (None, Some("c"))
+    ]
+  );
+
+  // unmatched items keep the `right` order, even when they share a key
+  let unmatched = vec![
+    Shipment {
+      order_id: 4,
+      tracking: "e",
+    },
+    Shipment {
+      order_id: 5,
+      tracking: "f",
+    },
+    Shipment {
+      order_id: 4,
+      tracking: "g",
+    },
+  ];
+  assert_eq!(
+    ids(full_join(&orders, &unmatched), order_id, tracking),
+    vec![
+      (Some(1), None),
+      (Some(2), None),
+      (Some(3), None),
+      (None, Some("e")),
+      (None, Some("f")),
+      (None, Some("g"))
+    ]
+  );
+}
+
+#[test]
+fn test_group_join() {
+  let (orders, shipments) = fixtures();
+  let groups: Vec<_> = group_join(&orders, &shipments)
+    .map(|(key, o, s)| {
+      (
+        key,
+        o.len(),
+        s.iter().map(|s| s.tracking).collect::<Vec<_>>(),
+      )
+    })
+    .collect();
+  assert_eq!(
+    groups,
+    vec![
+      (1, 1, vec!["b"]),
+      (2, 1, vec![]),
+      (3, 1, vec!["a", "d"]),
+      (4, 0, vec!["c"])
+    ]
+  );
+}
@@ -0,0 +1,96 @@
+use s2_utils::list::KeyMatcher;
+
+#[derive(Debug, PartialEq)]
+struct Listing {
+  id: Option<i64>,
+  sku: Option<&'static str>,
+  barcode: Option<&'static str>,
+}
+
+fn listing(id: Option<i64>, sku: Option<&'static str>, barcode: Option<&'static str>) -> Listing {
+  Listing { id, sku, barcode }
+}
+
+// each key has its own type
+fn matcher() -> KeyMatcher<Listing, Listing> {
+  KeyMatcher::new()
+    .key("id", |l: &Listing| l.id, |l: &Listing| l.id)
+    .key("sku", |l: &Listing| l.sku, |l: &Listing| l.sku)
+    .key("barcode", |l: &Listing| l.barcode, |l: &Listing| l.barcode)
+}
+
+#[test]
+fn test_key_matcher() {
+  let existing = vec![
+    listing(Some(1), Some("a"), None),
+    listing(None, Some("b"), Some("0001")),
+    listing(None, None, Some("0002")),
+    listing(Some(4), Some("d"), None),
+  ];
+  let new = vec![
+    listing(None, None, Some("0002")),
+    // matched by id before sku "b" is tried
+    listing(Some(1), Some("b"), None),
+    listing(None, Some("b"), None),
+    listing(None, None, Some("0009")),
+  ];
+
+  let changes = matcher().get_changed_items(&existing, &new);
+  assert_eq!(
+    changes.update,
+    vec![
+      (&existing[2], &new[0], "barcode"),
+      (&existing[0], &new[1], "id"),
+      (&existing[1], &new[2], "sku"),
+    ]
+  );
+  assert_eq!(changes.add, vec![&new[3]]);
+  assert_eq!(changes.delete, vec![&existing[3]]);
+
+  let changes = changes.into_changed_items();
+  assert_eq!(changes.update.len(), 3);
+}
+
+#[test]
+fn test_key_matcher_greedy() {
+  // the first unmatched existing item with the key is used
+  let existing = vec![
+    listing(None, Some("a"), None),
+    listing(None, Some("a"), None),
+  ];
+  let new = vec![listing(None, Some("a"), None)];
+  let changes = matcher().get_changed_items(&existing, &new);
+  assert_eq!(changes.update, vec![(&existing[0], &new[0], "sku")]);
+  assert_eq!(changes.delete, vec![&existing[1]]);
+
+  let changes = KeyMatcher::new().get_changed_items(&existing, &new);
+  assert_eq!(changes.add.len(), 1);
+  assert_eq!(changes.delete.len(), 2);
+}
+
+#[test]
+fn test_key_matcher_conflicts() {
+  // different ids block the match by sku
+  let matcher = matcher();
+  let existing = vec![listing(Some(4), Some("d"), None)];
+  let new = vec![listing(Some(5), Some("d"), None)];
+  let changes = matcher.get_changed_items(&existing, &new);
+  assert!(changes.update.is_empty());
+  assert_eq!(changes.add, vec![&new[0]]);
+  assert_eq!(changes.delete, vec![&existing[0]]);
+
+  // a missing id doesn't block it, the next candidate without a conflict is
+  // used. The matcher is reused for other lists.
+  let existing = vec![
+    listing(Some(4), Some("d"), None),
+    listing(None, Some("d"), None),
+  ];
+  let changes = matcher.get_changed_items(&existing, &new);
+  assert_eq!(changes.update, vec![(&existing[1], &new[0], "sku")]);
+  assert_eq!(changes.delete, vec![&existing[0]]);
+
+  let changes = matcher
+    .allow_key_conflicts()
+    .get_changed_items(&existing, &new);
+  assert_eq!(changes.update, vec![(&existing[0], &new[0], "sku")]);
+}
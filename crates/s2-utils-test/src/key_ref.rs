@@ -0,0 +1,141 @@
+use std::ptr;
+
+use s2_utils::list::{
+  get_changed_items, get_changed_items_hashed, get_changed_items_ref, get_dup_items,
+  get_dup_items_ref, inner_join, HasItemKey, HasItemKeyRef, KeyMatcher,
+};
+use s2_utils::GroupByKey;
+use s2_utils_derive::{HasItemKey, HasItemKeyRef};
+
+#[derive(Debug, PartialEq, HasItemKey, HasItemKeyRef)]
+struct Product {
+  #[item_key]
+  sku: String,
+  qty: i32,
+}
+
+#[derive(Debug, PartialEq, HasItemKeyRef)]
+struct Listing {
+  #[item_key(borrow = "str")]
+  sku: String,
+}
+
+#[derive(Debug, PartialEq, HasItemKeyRef)]
+struct Code(i32, #[item_key] &'static str);
+
+fn product(sku: &str, qty: i32) -> Product {
+  Product {
+    sku: sku.to_string(),
+    qty,
+  }
+}
+
+fn listing(sku: &str) -> Listing {
+  Listing {
+    sku: sku.to_string(),
+  }
+}
+
+#[test]
+fn test_derive_item_key_ref() {
+  let p = product("a", 1);
+  let sku: &String = p.get_item_key_ref();
+  assert_eq!(sku, "a");
+  let sku: &String = HasItemKey::get_item_key(&&p);
+  assert_eq!(sku, "a");
+
+  let l = listing("b");
+  let sku: &str = l.get_item_key_ref();
+  assert_eq!(sku, "b");
+  let sku: &str = (&l).get_item_key();
+  assert_eq!(sku, "b");
+
+  let code = Code(1, "x");
+  let key: &&str = code.get_item_key_ref();
+  assert_eq!(*key, "x");
+}
+
+#[test]
+fn test_borrowed_key_algorithms() {
+  let existing = vec![product("a", 1), product("b", 1), product("a", 2)];
+  let new = vec![product("b", 2), product("c", 1)];
+
+  let owned = get_changed_items::<String, _, _>(&existing, &new);
+  let borrowed = get_changed_items::<&String, _, _>(&existing, &new);
+  assert_eq!(borrowed.add, owned.add);
+  assert_eq!(borrowed.update, owned.update);
+  assert_eq!(borrowed.delete, owned.delete);
+
+  let dups = get_dup_items::<&String, _>(&existing);
+  assert_eq!(
+    dups,
+    vec![(&existing[0].sku, vec![(0, &existing[0]), (2, &existing[2])])]
+  );
+
+  // `HasItemKeyRef` only
+  let borrowed = get_changed_items_ref::<String, _, _>(&existing, &new);
+  assert_eq!(borrowed.add, owned.add);
+  assert_eq!(borrowed.update, owned.update);
+  assert_eq!(borrowed.delete, owned.delete);
+  let dups = get_dup_items_ref::<String, _>(&existing);
+  assert_eq!(
+    dups,
+    vec![(&existing[0].sku, vec![(0, &existing[0]), (2, &existing[2])])]
+  );
+
+  let groups: Vec<_> = existing
+    .iter()
+    .group_by_key(|&p| -> &String { p.get_item_key_ref() })
+    .map(|(sku, items)| (sku, items.len()))
+    .collect();
+  assert_eq!(groups, vec![(&existing[0].sku, 2), (&existing[1].sku, 1)]);
+
+  // only the borrowed flavour, the key type is inferred
+  let listings = vec![listing("b"), listing("c")];
+  let new_listings = vec![listing("c"), listing("d")];
+  let changes = get_changed_items_hashed(&listings, &new_listings);
+  assert_eq!(changes.add, vec![&new_listings[1]]);
+  assert_eq!(changes.update, vec![(&listings[1], &new_listings[0])]);
+  assert_eq!(changes.delete, vec![&listings[0]]);
+
+  let pairs: Vec<_> = inner_join::<&str, _, _>(&listings, &listings).collect();
+  assert_eq!(
+    pairs,
+    vec![(&listings[0], &listings[0]), (&listings[1], &listings[1])]
+  );
+
+  let matched = KeyMatcher::new()
+    .key(
+      "sku",
+      |l: &Listing| Some(l.sku.clone()),
+      |p: &Product| Some(p.sku.clone()),
+    )
+    .get_changed_items(&listings, &new);
+  assert_eq!(
+    matched.update,
+    vec![
+      (&listings[0], &new[0], "sku"),
+      (&listings[1], &new[1], "sku")
+    ]
+  );
+}
+
+#[test]
+fn test_borrowed_string_keys() {
+  let existing = vec!["a".to_string(), "b".to_string(), "a".to_string()];
+  let new = vec!["b".to_string(), "c".to_string()];
+
+  let owned = get_changed_items(&existing, &new);
+  let borrowed = get_changed_items_ref::<str, _, _>(&existing, &new);
+  assert_eq!(borrowed.add, owned.add);
+  assert_eq!(borrowed.update, owned.update);
+  assert_eq!(borrowed.delete, owned.delete);
+
+  // the keys point into the items, no string was cloned
+  let dups = get_dup_items_ref::<str, _>(&existing);
+  assert_eq!(
+    dups,
+    vec![("a", vec![(0, &existing[0]), (2, &existing[2])])]
+  );
+  assert!(ptr::eq(dups[0].0, existing[0].as_str()));
+}
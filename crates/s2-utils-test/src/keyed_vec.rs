@@ -0,0 +1,116 @@
+use s2_utils::list::{KeyMode, KeyedVec};
+use s2_utils_derive::HasItemKey;
+
+#[derive(Debug, Clone, HasItemKey, PartialEq)]
+#[has_item_key(String, expr = "self.0.to_string()")]
+struct Sku(&'static str, i32);
+
+#[test]
+fn test_keyed_vec_unique() {
+  let mut list = KeyedVec::new();
+  assert!(list.push(Sku("a", 1)).is_ok());
+  assert!(list.push(Sku("b", 2)).is_ok());
+  assert_eq!(list.push(Sku("a", 3)), Err(Sku("a", 3)));
+  assert_eq!(list.len(), 2);
+
+  assert_eq!(list.get("a"), Some(&Sku("a", 1)));
+  assert_eq!(list.upsert(Sku("a", 3)), Some(Sku("a", 1)));
+  assert_eq!(list.upsert(Sku("c", 4)), None);
+  assert_eq!(list.update("b", |sku| sku.1 = 5), Some(Ok(())));
+  assert_eq!(list.update("x", |sku| sku.1 = 5), None);
+  assert_eq!(
+    list.iter().collect::<Vec<_>>(),
+    vec![&Sku("a", 3), &Sku("b", 5), &Sku("c", 4)]
+  );
+
+  assert_eq!(list.remove("b"), Some(Sku("b", 5)));
+  assert_eq!(list.remove("b"), None);
+  assert!(!list.contains_key("b"));
+  assert_eq!(list.len(), 2);
+  assert_eq!(list.into_vec(), vec![Sku("a", 3), Sku("c", 4)]);
+}
+
+#[test]
+fn test_keyed_vec_compaction() {
+  let mut list = KeyedVec::new();
+  for i in 0..100 {
+    list.push(i).unwrap();
+  }
+  for i in 0..100 {
+    if i % 3 != 0 {
+      assert_eq!(list.remove(&i), Some(i));
+    }
+  }
+  assert_eq!(list.len(), 34);
+  for i in 0..100 {
+    assert_eq!(list.get(&i).is_some(), i % 3 == 0);
+  }
+  list.upsert(1);
+  assert_eq!(
+    list.iter().cloned().collect::<Vec<_>>(),
+    (0..100)
+      .filter(|i| i % 3 == 0)
+      .chain(vec![1])
+      .collect::<Vec<_>>()
+  );
+}
+
+#[test]
+fn test_keyed_vec_multi() {
+  let mut list = KeyedVec::with_mode(KeyMode::Multi);
+  for item in [
+    Sku("a", 1),
+    Sku("b", 2),
+    Sku("a", 3),
+    Sku("c", 4),
+    Sku("b", 5),
+  ] {
+    list.push(item).unwrap();
+  }
+  assert_eq!(list.get_all("a"), vec![&Sku("a", 1), &Sku("a", 3)]);
+  assert_eq!(list.remove("a"), Some(Sku("a", 1)));
+  assert_eq!(
+    list.get_dup_items(),
+    vec![(&"b".to_string(), vec![(0, &Sku("b", 2)), (3, &Sku("b", 5))])]
+  );
+  assert_eq!(list.remove_all("b"), vec![Sku("b", 2), Sku("b", 5)]);
+  assert_eq!(list.len(), 2);
+}
+
+#[test]
+fn test_keyed_vec_get_changed_items() {
+  let mut list = KeyedVec::new();
+  for item in [Sku("a", 1), Sku("b", 2), Sku("c", 3)] {
+    list.push(item).unwrap();
+  }
+  list.remove("a");
+
+  let new_items = vec![Sku("c", 4), Sku("d", 5)];
+  let changes = list.get_changed_items(&new_items);
+  assert_eq!(changes.add, vec![&Sku("d", 5)]);
+  assert_eq!(changes.update, vec![(&Sku("c", 3), &Sku("c", 4))]);
+  assert_eq!(changes.delete, vec![&Sku("b", 2)]);
+}
+
+#[test]
+fn test_keyed_vec_update_key() {
+  let mut list = KeyedVec::with_mode(KeyMode::Multi);
+  for sku in [Sku("a", 1), Sku("b", 2), Sku("a", 3)] {
+    list.push(sku).unwrap();
+  }
+  // changing the key moves the item to the new key, keeping its position
+  assert_eq!(list.update("a", |sku| sku.0 = "b"), Some(Ok(())));
+  assert_eq!(list.get_all("a"), vec![&Sku("a", 3)]);
+  assert_eq!(list.get_all("b"), vec![&Sku("b", 1), &Sku("b", 2)]);
+  assert_eq!(list.get_dup_items().len(), 1);
+
+  let mut list = KeyedVec::new();
+  list.push(Sku("a", 1)).unwrap();
+  list.push(Sku("b", 2)).unwrap();
+  assert_eq!(list.update("a", |sku| sku.0 = "c"), Some(Ok(())));
+  assert!(!list.contains_key("a"));
+  assert_eq!(list.get("c"), Some(&Sku("c", 1)));
+  // the new key is taken: the item is removed
+  assert_eq!(list.update("c", |sku| sku.0 = "b"), Some(Err(Sku("b", 1))));
+  assert_eq!(list.into_vec(), vec![Sku("b", 2)]);
+}
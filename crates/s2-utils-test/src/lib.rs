@@ -1 +1,42 @@
-mod list;
\ No newline at end of file
+#[cfg(test)]
+mod batch;
+#[cfg(test)]
+mod by_key;
+#[cfg(test)]
+mod changeset;
+#[cfg(test)]
+mod dedup;
+#[cfg(test)]
+mod duplicate;
+#[cfg(test)]
+mod group;
+#[cfg(test)]
+mod join;
+#[cfg(test)]
+mod key_matcher;
+#[cfg(test)]
+mod key_ref;
+#[cfg(test)]
+mod keyed_vec;
+#[cfg(test)]
+mod list;
+#[cfg(test)]
+mod merge3;
+#[cfg(test)]
+mod nested;
+#[cfg(test)]
+mod normalize;
+#[cfg(test)]
+mod ordered;
+#[cfg(test)]
+mod parallel;
+#[cfg(test)]
+mod reconcile;
+#[cfg(test)]
+mod snapshot;
+#[cfg(test)]
+mod sorted;
+#[cfg(test)]
+mod support;
+#[cfg(test)]
+mod try_key;
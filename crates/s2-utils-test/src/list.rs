@@ -1,4 +1,4 @@
-use s2_utils_derive::HasItemKey;
+use s2_utils_derive::{DiffFields, HasItemKey};
 
 #[derive(Debug, HasItemKey, PartialEq)]
 #[has_item_key(i32, expr = "self.0")]
@@ -79,4 +79,126 @@ fn test_get_item_changes() {
     assert_eq!(changes.delete, vec![&A(2), &A(2)]);
     assert_eq!(changes.update, vec![(&A(1), &B(1)), (&A(3), &B(3)),])
   }
+
+  // dup existing and new items, extra existing items are deleted once per
+  // new item
+  {
+    let l1 = vec![A(1), A(2), A(2), A(3)];
+    let l2 = vec![B(1), B(2), B(2), B(3)];
+
+    let changes = get_changed_items(&l1, &l2);
+    assert!(changes.add.is_empty());
+    assert_eq!(changes.delete, vec![&A(2), &A(2)]);
+    assert!(std::ptr::eq(changes.delete[0], changes.delete[1]));
+    assert_eq!(
+      changes.update,
+      vec![(&A(1), &B(1)), (&A(2), &B(2)), (&A(3), &B(3)),]
+    )
+  }
+}
+
+#[test]
+fn test_get_changed_items_hashed() {
+  use s2_utils::list::{get_changed_items, get_changed_items_hashed};
+
+  let cases = vec![
+    (vec![A(1), A(2), A(3)], vec![B(1), B(2), B(3)]),
+    (vec![A(1), A(3)], vec![B(1), B(2), B(3)]),
+    (vec![A(1), A(2), A(3)], vec![B(1), B(3)]),
+    (vec![A(1), A(2), A(2), A(3)], vec![B(1), B(3)]),
+    (vec![A(1), A(2), A(2), A(3)], vec![B(1), B(2), B(3)]),
+    (vec![A(1), A(2), A(2), A(3)], vec![B(1), B(3), B(3)]),
+    (
+      vec![A(2), A(1), A(2), A(2)],
+      vec![B(4), B(2), B(1), B(2), B(4)],
+    ),
+    (vec![], vec![B(1), B(1)]),
+    (vec![A(1), A(1)], vec![]),
+  ];
+
+  for (l1, l2) in &cases {
+    let expected = get_changed_items(l1, l2);
+    let changes = get_changed_items_hashed(l1, l2);
+    assert_eq!(changes.add, expected.add);
+    assert_eq!(changes.update, expected.update);
+    assert_eq!(changes.delete, expected.delete);
+  }
+}
+
+#[derive(Debug, HasItemKey, DiffFields, PartialEq)]
+#[has_item_key(i32, expr = "self.id")]
+struct Row {
+  id: i32,
+  name: &'static str,
+  qty: i32,
+  #[diff_fields(skip)]
+  updated_at: i64,
+}
+
+#[derive(Debug, DiffFields, PartialEq)]
+struct Pair(i32, #[diff_fields(skip)] i32, &'static str);
+
+#[test]
+fn test_diff_fields() {
+  use s2_utils::list::DiffFields;
+
+  assert!(Pair(1, 2, "a").diff_fields(&Pair(1, 3, "a")).is_empty());
+  assert_eq!(
+    Pair(1, 2, "a").diff_fields(&Pair(2, 2, "b")),
+    vec!["0", "2"]
+  );
+}
+
+#[test]
+fn test_get_field_changed_items() {
+  use s2_utils::list::get_field_changed_items;
+
+  let row = |id, name, qty, updated_at| Row {
+    id,
+    name,
+    qty,
+    updated_at,
+  };
+  let l1 = vec![row(1, "a", 1, 0), row(2, "b", 2, 0), row(3, "c", 3, 0)];
+  let l2 = vec![row(1, "a", 1, 1), row(2, "b", 5, 1), row(4, "d", 4, 1)];
+
+  let changes = get_field_changed_items(&l1, &l2);
+  assert_eq!(changes.add, vec![&l2[2]]);
+  assert_eq!(changes.update, vec![(&l1[1], &l2[1], vec!["qty"])]);
+  assert_eq!(changes.unchanged, vec![(&l1[0], &l2[0])]);
+  assert_eq!(changes.delete, vec![&l1[2]]);
+}
+
+#[derive(Debug, HasItemKey, PartialEq)]
+struct Line {
+  #[item_key]
+  order_id: i32,
+  #[item_key]
+  sku: String,
+  qty: i32,
+}
+
+#[derive(Debug, HasItemKey, PartialEq)]
+struct Sku(#[item_key] String, i32);
+
+#[test]
+fn test_derive_item_key_fields() {
+  use s2_utils::list::{get_changed_items, HasItemKey};
+
+  let sku = Sku("a".to_string(), 1);
+  assert_eq!(sku.get_item_key(), "a".to_string());
+
+  let line = |order_id, sku: &str, qty| Line {
+    order_id,
+    sku: sku.to_string(),
+    qty,
+  };
+  assert_eq!(line(1, "a", 2).get_item_key(), (1, "a".to_string()));
+
+  let l1 = vec![line(1, "a", 1), line(1, "b", 1), line(2, "a", 1)];
+  let l2 = vec![line(1, "b", 2), line(2, "a", 2), line(2, "b", 1)];
+  let changes = get_changed_items(&l1, &l2);
+  assert_eq!(changes.add, vec![&l2[2]]);
+  assert_eq!(changes.update, vec![(&l1[1], &l2[0]), (&l1[2], &l2[1])]);
+  assert_eq!(changes.delete, vec![&l1[0]]);
 }
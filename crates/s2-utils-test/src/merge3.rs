@@ -0,0 +1,57 @@
+use s2_utils::list::{merge3, merge3_with, Merge3Kind, Merge3Resolution};
+use s2_utils_derive::HasItemKey;
+
+#[derive(Debug, HasItemKey, PartialEq)]
+#[has_item_key(i32, expr = "self.0")]
+struct A(i32, &'static str);
+
+#[test]
+fn test_merge3() {
+  let base = vec![A(1, "a"), A(2, "b"), A(3, "c"), A(4, "d"), A(5, "e")];
+  let local = vec![A(1, "a"), A(2, "x"), A(3, "y"), A(4, "d"), A(6, "f")];
+  let remote = vec![A(1, "a"), A(2, "b"), A(3, "z"), A(5, "w"), A(6, "f")];
+
+  let items = merge3(&base, &local, &remote);
+  let summary: Vec<_> = items
+    .iter()
+    .map(|i| (i.key, i.kind, i.merged.map(|m| m.map(|m| m.1))))
+    .collect();
+  assert_eq!(
+    summary,
+    vec![
+      (1, Merge3Kind::Unchanged, Some(Some("a"))),
+      (2, Merge3Kind::Local, Some(Some("x"))),
+      (3, Merge3Kind::Conflict, None),
+      // deleted by remote
+      (4, Merge3Kind::Remote, Some(None)),
+      // deleted by local, changed by remote
+      (5, Merge3Kind::Conflict, None),
+      (6, Merge3Kind::Both, Some(Some("f"))),
+    ]
+  );
+  assert!(!items[2].is_resolved());
+  assert_eq!(items[2].base, Some(&A(3, "c")));
+  assert_eq!(items[2].local, Some(&A(3, "y")));
+  assert_eq!(items[2].remote, Some(&A(3, "z")));
+}
+
+#[test]
+fn test_merge3_with_resolver() {
+  let base = vec![A(1, "a"), A(2, "b")];
+  let local = vec![A(1, "x"), A(2, "y")];
+  let remote = vec![A(1, "z")];
+
+  let mut conflicts = vec![];
+  let items = merge3_with(&base, &local, &remote, |item| {
+    conflicts.push(item.key);
+    if item.remote.is_some() {
+      Merge3Resolution::Remote
+    } else {
+      Merge3Resolution::Local
+    }
+  });
+  assert_eq!(conflicts, vec![1, 2]);
+  assert_eq!(items[0].merged, Some(Some(&A(1, "z"))));
+  assert_eq!(items[1].merged, Some(Some(&A(2, "y"))));
+  assert!(items.iter().all(|i| i.kind == Merge3Kind::Conflict));
+}
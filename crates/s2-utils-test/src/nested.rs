@@ -0,0 +1,189 @@
+use s2_utils::list::{
+  child_changed_items, get_changed_items_hashed, nested_child_changed_items, ChangedItems,
+  DiffChildren, HasChildItems, NestedChangedItems,
+};
+use s2_utils_derive::HasItemKey;
+
+#[derive(Debug, PartialEq, HasItemKey)]
+struct Order {
+  #[item_key]
+  id: i32,
+  lines: Vec<Line>,
+}
+
+#[derive(Debug, PartialEq, HasItemKey)]
+struct Line {
+  #[item_key]
+  sku: &'static str,
+  qty: i32,
+}
+
+impl HasChildItems<Line> for Order {
+  fn get_child_items(&self) -> &[Line] {
+    &self.lines
+  }
+}
+
+impl<'a> DiffChildren<'a> for Order {
+  type Changes = ChangedItems<'a, Line, Line>;
+
+  fn diff_children(existing: Option<&'a Order>, new: Option<&'a Order>) -> Self::Changes {
+    child_changed_items(existing, new)
+  }
+}
+
+fn order(id: i32, lines: &[(&'static str, i32)]) -> Order {
+  Order {
+    id,
+    lines: lines.iter().map(|&(sku, qty)| Line { sku, qty }).collect(),
+  }
+}
+
+#[test]
+fn test_nested_changes() {
+  let existing = vec![
+    order(1, &[("a", 1), ("b", 1)]),
+    order(2, &[("a", 1)]),
+    order(3, &[("c", 1)]),
+  ];
+  let new = vec![
+    order(1, &[("b", 2), ("c", 1)]),
+    order(3, &[("c", 1)]),
+    order(4, &[("a", 1), ("d", 1)]),
+  ];
+  let changes = get_changed_items_hashed(&existing, &new).nested_changes();
+
+  assert_eq!(changes.add.len(), 1);
+  let (added, lines) = &changes.add[0];
+  assert_eq!(added.id, 4);
+  assert_eq!(lines.add, new[2].lines.iter().collect::<Vec<_>>());
+  assert!(lines.update.is_empty() && lines.delete.is_empty());
+
+  assert_eq!(changes.delete.len(), 1);
+  let (deleted, lines) = &changes.delete[0];
+  assert_eq!(deleted.id, 2);
+  assert_eq!(lines.delete, vec![&existing[1].lines[0]]);
+  assert!(lines.add.is_empty() && lines.update.is_empty());
+
+  assert_eq!(changes.update.len(), 2);
+  let (ei, ni, lines) = &changes.update[0];
+  assert_eq!((ei.id, ni.id), (1, 1));
+  assert_eq!(lines.add, vec![&new[0].lines[1]]);
+  assert_eq!(
+    lines.update,
+    vec![(&existing[0].lines[1], &new[0].lines[0])]
+  );
+  assert_eq!(lines.delete, vec![&existing[0].lines[0]]);
+
+  // or without `DiffChildren`
+  let counts = get_changed_items_hashed(&existing, &new).with_child_changes(|ei, ni| {
+    let count = |o: Option<&Order>| o.map_or(0, |o| o.lines.len());
+    (count(ei), count(ni))
+  });
+  assert_eq!(counts.add[0].1, (0, 2));
+  assert_eq!(counts.update[0].2, (2, 2));
+  assert_eq!(counts.delete[0].1, (1, 0));
+}
+
+#[derive(Debug, PartialEq, HasItemKey)]
+struct Product {
+  #[item_key]
+  id: i32,
+  variants: Vec<Variant>,
+  images: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, HasItemKey)]
+struct Variant {
+  #[item_key]
+  sku: &'static str,
+  images: Vec<String>,
+}
+
+impl HasChildItems<Variant> for Product {
+  fn get_child_items(&self) -> &[Variant] {
+    &self.variants
+  }
+}
+
+impl HasChildItems<String> for Product {
+  fn get_child_items(&self) -> &[String] {
+    &self.images
+  }
+}
+
+impl HasChildItems<String> for Variant {
+  fn get_child_items(&self) -> &[String] {
+    &self.images
+  }
+}
+
+impl<'a> DiffChildren<'a> for Product {
+  type Changes = (
+    NestedChangedItems<'a, Variant, Variant, ChangedItems<'a, String, String>>,
+    ChangedItems<'a, String, String>,
+  );
+
+  fn diff_children(existing: Option<&'a Product>, new: Option<&'a Product>) -> Self::Changes {
+    (
+      nested_child_changed_items(existing, new),
+      child_changed_items(existing, new),
+    )
+  }
+}
+
+impl<'a> DiffChildren<'a> for Variant {
+  type Changes = ChangedItems<'a, String, String>;
+
+  fn diff_children(existing: Option<&'a Variant>, new: Option<&'a Variant>) -> Self::Changes {
+    child_changed_items(existing, new)
+  }
+}
+
+fn images(images: &[&str]) -> Vec<String> {
+  images.iter().map(|i| i.to_string()).collect()
+}
+
+#[test]
+fn test_nested_changes_tree() {
+  let existing = vec![Product {
+    id: 1,
+    variants: vec![Variant {
+      sku: "a",
+      images: images(&["a1.png", "a2.png"]),
+    }],
+    images: images(&["a.png", "b.png"]),
+  }];
+  let new = vec![Product {
+    id: 1,
+    variants: vec![
+      Variant {
+        sku: "a",
+        images: images(&["a2.png"]),
+      },
+      Variant {
+        sku: "b",
+        images: images(&["b1.png"]),
+      },
+    ],
+    images: images(&["b.png"]),
+  }];
+  // one changeset, the product update carries both collections
+  let changes = get_changed_items_hashed(&existing, &new).nested_changes();
+  assert_eq!(changes.update.len(), 1);
+  let (_, _, (variants, product_images)) = &changes.update[0];
+
+  assert_eq!(product_images.delete, vec![&existing[0].images[0]]);
+  assert_eq!(product_images.update.len(), 1);
+
+  // the variant changes carry their image changes
+  let (_, _, variant_images) = &variants.update[0];
+  assert_eq!(
+    variant_images.delete,
+    vec![&existing[0].variants[0].images[0]]
+  );
+  assert_eq!(variant_images.update.len(), 1);
+  let (added, variant_images) = &variants.add[0];
+  assert_eq!(added.sku, "b");
+  assert_eq!(variant_images.add, vec![&new[0].variants[1].images[0]]);
+}
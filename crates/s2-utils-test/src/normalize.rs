@@ -0,0 +1,92 @@
+use s2_utils::list::{get_changed_items, KeyNormalizer};
+use s2_utils_derive::HasItemKey;
+
+#[derive(Debug, PartialEq, HasItemKey)]
+struct Listing {
+  #[item_key]
+  sku: String,
+  qty: i32,
+}
+
+fn listing(sku: &str, qty: i32) -> Listing {
+  Listing {
+    sku: sku.to_string(),
+    qty,
+  }
+}
+
+#[test]
+fn test_key_normalizer() {
+  let normalizer = KeyNormalizer::new()
+    .trim()
+    .lowercase()
+    .custom(|key| key.replace('-', ""));
+  assert_eq!(normalizer.normalize(" AB-1 ".to_string()), "ab1");
+
+  let existing = vec![listing("AB-1", 1), listing("cd2", 1), listing("ef3", 1)];
+  let new = vec![listing(" ab1", 2), listing("CD2 ", 2), listing("gh4", 2)];
+  assert_eq!(get_changed_items(&existing, &new).update.len(), 0);
+
+  let changes = normalizer.get_changed_items(&existing, &new);
+  let update: Vec<_> = changes
+    .update
+    .iter()
+    .map(|(ei, ni)| (ei.key.as_str(), ni.key.as_str(), ni.normalized_key.as_str()))
+    .collect();
+  assert_eq!(
+    update,
+    vec![("AB-1", " ab1", "ab1"), ("cd2", "CD2 ", "cd2")]
+  );
+  assert_eq!(changes.add[0].key, "gh4");
+  assert_eq!(changes.delete[0].key, "ef3");
+
+  let changes = changes.into_changed_items();
+  assert_eq!(
+    changes.update,
+    vec![(&existing[0], &new[0]), (&existing[1], &new[1])]
+  );
+  assert_eq!(changes.add, vec![&new[2]]);
+  assert_eq!(changes.delete, vec![&existing[2]]);
+
+  // the extra existing item of a key is deleted once per new item with the key
+  let dup_existing = vec![listing("ab1", 1), listing("AB1", 1)];
+  let dup_new = vec![listing(" ab1", 2), listing("AB-1", 2)];
+  let changes = normalizer.get_changed_items(&dup_existing, &dup_new);
+  let delete: Vec<_> = changes.delete.iter().map(|ei| ei.key.as_str()).collect();
+  assert_eq!(delete, vec!["AB1", "AB1"]);
+  assert_eq!(changes.update[0].1.key, "AB-1");
+
+  // wrapped items work with the other list functions
+  let wrapped_existing = normalizer.wrap(&existing);
+  let wrapped_new = normalizer.wrap(&new);
+  let changes = get_changed_items(&wrapped_existing, &wrapped_new);
+  assert_eq!(changes.update.len(), 2);
+}
+
+#[test]
+fn test_key_normalizer_nfkc() {
+  let normalizer = KeyNormalizer::new().nfkc().lowercase();
+  // full width letters and digits
+  assert_eq!(normalizer.normalize("ＡＢ１".to_string()), "ab1");
+  let existing = vec![listing("ab1", 1)];
+  let new = vec![listing("ＡＢ１", 2)];
+  let changes = normalizer.get_changed_items(&existing, &new);
+  assert_eq!(changes.update.len(), 1);
+  assert_eq!(changes.update[0].1.key, "ＡＢ１");
+}
+
+#[test]
+fn test_key_normalizer_casefold() {
+  let normalizer = KeyNormalizer::new().casefold();
+  assert_eq!(normalizer.normalize("Straße".to_string()), "strasse");
+  let existing = vec![listing("STRASSE", 1)];
+  let new = vec![listing("Straße", 2)];
+  let changes = normalizer.get_changed_items(&existing, &new);
+  assert_eq!(changes.update.len(), 1);
+  assert_eq!(changes.update[0].1.key, "Straße");
+  // lowercasing alone keeps them apart
+  let changes = KeyNormalizer::new()
+    .lowercase()
+    .get_changed_items(&existing, &new);
+  assert!(changes.update.is_empty());
+}
@@ -0,0 +1,40 @@
+use s2_utils::list::get_ordered_changed_items;
+
+fn moves(l1: &[&str], l2: &[&str]) -> Vec<(usize, usize)> {
+  let l1: Vec<String> = l1.iter().map(|s| s.to_string()).collect();
+  let l2: Vec<String> = l2.iter().map(|s| s.to_string()).collect();
+  get_ordered_changed_items(&l1, &l2).moves
+}
+
+#[test]
+fn test_get_ordered_changed_items() {
+  let l1 = vec![1, 2, 3, 4, 5];
+  let l2 = vec![2, 6, 3, 1, 5];
+
+  let changes = get_ordered_changed_items(&l1, &l2);
+  assert_eq!(changes.add, vec![(1, &6)]);
+  assert_eq!(changes.delete, vec![(3, &4)]);
+  assert_eq!(
+    changes.update,
+    vec![
+      (1, &2, 0, &2),
+      (2, &3, 2, &3),
+      (0, &1, 3, &1),
+      (4, &5, 4, &5),
+    ]
+  );
+  assert_eq!(changes.moves, vec![(0, 3)]);
+}
+
+#[test]
+fn test_ordered_moves() {
+  assert!(moves(&["a", "b", "c"], &["a", "b", "c"]).is_empty());
+  assert!(moves(&["a", "b", "c"], &["a", "x", "c"]).is_empty());
+  assert_eq!(moves(&["a", "b", "c"], &["c", "a", "b"]), vec![(2, 0)]);
+  assert_eq!(
+    moves(&["a", "b", "c", "d"], &["d", "c", "b", "a"]),
+    vec![(3, 0), (2, 1), (1, 2)]
+  );
+  // duplicated keys are matched in order
+  assert_eq!(moves(&["a", "b", "a"], &["a", "a", "b"]), vec![(2, 1)]);
+}
@@ -0,0 +1,94 @@
+use s2_utils::list::{
+  dedup_map, get_changed_items, get_dup_items, par_dedup_map, par_get_changed_items,
+  par_get_dup_items, par_group_by_key,
+};
+use s2_utils::GroupByKey;
+use s2_utils_derive::HasItemKey;
+
+use crate::support::Lcg;
+
+#[derive(Debug, Clone, PartialEq, HasItemKey)]
+struct Item {
+  #[item_key]
+  key: String,
+  value: u32,
+}
+
+// deterministic pseudo random lists, with duplicated keys
+fn items(seed: u64, len: usize, keys: u64) -> Vec<Item> {
+  let mut rng = Lcg::new(seed);
+  (0..len)
+    .map(|_| {
+      let state = rng.next();
+      Item {
+        key: format!("k{}", (state >> 33) % keys),
+        value: (state >> 16) as u32 % 10,
+      }
+    })
+    .collect()
+}
+
+// runs `check` with list pairs of various sizes and key densities
+fn check_all<F>(check: F)
+where
+  F: Fn(&[Item], &[Item]),
+{
+  for &(len, keys) in &[
+    (0, 1),
+    (1, 1),
+    (10, 3),
+    (100, 50),
+    (1000, 300),
+    (5000, 5000),
+  ] {
+    for seed in 0..3 {
+      let existing = items(seed, len, keys);
+      let new = items(seed + 100, len * 2 / 3, keys);
+      check(&existing, &new);
+    }
+  }
+}
+
+#[test]
+fn test_par_get_changed_items() {
+  check_all(|existing, new| {
+    let serial = get_changed_items(existing, new);
+    let parallel = par_get_changed_items(existing, new);
+    assert_eq!(parallel.add, serial.add);
+    assert_eq!(parallel.update, serial.update);
+    assert_eq!(parallel.delete, serial.delete);
+  });
+}
+
+#[test]
+fn test_par_get_dup_items() {
+  check_all(|existing, _| {
+    let serial: Vec<(String, _)> = get_dup_items(existing);
+    assert_eq!(par_get_dup_items(existing), serial);
+  });
+}
+
+#[test]
+fn test_par_group_by_key() {
+  check_all(|existing, _| {
+    let serial: Vec<_> = existing.to_vec().group_by_key(|i| i.key.clone()).collect();
+    let parallel: Vec<_> = par_group_by_key(existing.to_vec(), |i| i.key.clone()).collect();
+    assert_eq!(parallel, serial);
+  });
+}
+
+#[test]
+fn test_par_dedup_map() {
+  check_all(|existing, _| {
+    let f = |i: &Item| {
+      if i.value == 0 {
+        vec![]
+      } else {
+        vec![i.value, i.value * 7 % 10]
+      }
+    };
+    let serial: Vec<u32> = dedup_map(existing, f);
+    let parallel: Vec<u32> = par_dedup_map(existing, f);
+    assert_eq!(parallel, serial);
+  });
+}
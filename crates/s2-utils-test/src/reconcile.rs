@@ -0,0 +1,146 @@
+use s2_utils::list::{get_changed_items, ApplyFailure, ChangeKind, ChangeSink, Reconciler};
+
+#[derive(Default)]
+struct Recorder {
+  calls: Vec<(ChangeKind, Vec<i32>)>,
+  fail_on: Option<i32>,
+}
+
+impl Recorder {
+  fn record(&mut self, kind: ChangeKind, keys: Vec<i32>) -> Result<(), String> {
+    if keys.iter().any(|k| Some(*k) == self.fail_on) {
+      return Err(format!("failed: {:?}", keys));
+    }
+    self.calls.push((kind, keys));
+    Ok(())
+  }
+}
+
+impl ChangeSink<i32, i32> for Recorder {
+  type Error = String;
+
+  fn insert(&mut self, items: &[&i32]) -> Result<(), String> {
+    self.record(ChangeKind::Insert, items.iter().map(|i| **i).collect())
+  }
+
+  fn update(&mut self, items: &[(&i32, &i32)]) -> Result<(), String> {
+    self.record(ChangeKind::Update, items.iter().map(|i| *i.0).collect())
+  }
+
+  fn delete(&mut self, items: &[&i32]) -> Result<(), String> {
+    self.record(ChangeKind::Delete, items.iter().map(|i| **i).collect())
+  }
+}
+
+#[test]
+fn test_reconciler_apply() {
+  let l1 = vec![1, 2, 3, 4];
+  let l2 = vec![3, 4, 5, 6, 7];
+  let changes = get_changed_items(&l1, &l2);
+
+  let mut sink = Recorder::default();
+  let report = Reconciler::new().apply(&changes, &mut sink);
+  assert!(report.is_ok());
+  assert_eq!(
+    sink.calls,
+    vec![
+      (ChangeKind::Insert, vec![5]),
+      (ChangeKind::Insert, vec![6]),
+      (ChangeKind::Insert, vec![7]),
+      (ChangeKind::Update, vec![3]),
+      (ChangeKind::Update, vec![4]),
+      (ChangeKind::Delete, vec![1]),
+      (ChangeKind::Delete, vec![2]),
+    ]
+  );
+  assert_eq!(report.applied.len(), 7);
+
+  let mut sink = Recorder::default();
+  let report = Reconciler::new()
+    .order([ChangeKind::Delete, ChangeKind::Update, ChangeKind::Insert])
+    .batch_size(2)
+    .apply(&changes, &mut sink);
+  assert!(report.is_ok());
+  assert_eq!(
+    sink.calls,
+    vec![
+      (ChangeKind::Delete, vec![1, 2]),
+      (ChangeKind::Update, vec![3, 4]),
+      (ChangeKind::Insert, vec![5, 6]),
+      (ChangeKind::Insert, vec![7]),
+    ]
+  );
+}
+
+#[test]
+fn test_reconciler_failures() {
+  let l1 = vec![1, 2];
+  let l2 = vec![2, 3, 4, 5];
+  let changes = get_changed_items(&l1, &l2);
+
+  let mut sink = Recorder {
+    fail_on: Some(4),
+    ..Default::default()
+  };
+  let report = Reconciler::new().batch_size(2).apply(&changes, &mut sink);
+  assert!(!report.is_ok());
+  match report.failed.as_slice() {
+    [ApplyFailure { kind, keys, error }] => {
+      assert_eq!(*kind, ChangeKind::Insert);
+      assert_eq!(keys, &vec![3, 4]);
+      assert_eq!(error, "failed: [3, 4]");
+    }
+    _ => panic!("one failure expected"),
+  }
+  assert_eq!(
+    report.applied,
+    vec![
+      (ChangeKind::Insert, 5),
+      (ChangeKind::Update, 2),
+      (ChangeKind::Delete, 1)
+    ]
+  );
+  assert!(report.skipped.is_empty());
+
+  let mut sink = Recorder {
+    fail_on: Some(4),
+    ..Default::default()
+  };
+  let report = Reconciler::new()
+    .stop_on_error(true)
+    .apply(&changes, &mut sink);
+  assert_eq!(report.applied, vec![(ChangeKind::Insert, 3)]);
+  assert_eq!(report.failed.len(), 1);
+  assert_eq!(
+    report.skipped,
+    vec![
+      (ChangeKind::Insert, 5),
+      (ChangeKind::Update, 2),
+      (ChangeKind::Delete, 1)
+    ]
+  );
+}
+
+#[test]
+fn test_reconciler_max_weight() {
+  let l1 = vec![1, 2];
+  let l2 = vec![3, 4, 5, 6];
+  let changes = get_changed_items(&l1, &l2);
+
+  // weighted by the item value, at most 10 per batch
+  let mut sink = Recorder::default();
+  let report = Reconciler::new()
+    .batch_size(3)
+    .max_weight(10, |ei: &i32| *ei as usize, |ni: &i32| *ni as usize)
+    .apply(&changes, &mut sink);
+  assert!(report.is_ok());
+  assert_eq!(
+    sink.calls,
+    vec![
+      (ChangeKind::Insert, vec![3, 4]),
+      (ChangeKind::Insert, vec![5]),
+      (ChangeKind::Insert, vec![6]),
+      (ChangeKind::Delete, vec![1, 2]),
+    ]
+  );
+}
@@ -0,0 +1,72 @@
+use std::fs;
+use std::path::PathBuf;
+
+use s2_utils::snapshot::{SnapshotError, SnapshotStore};
+
+use crate::support::{item, Item};
+
+fn temp_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("s2-utils-{}-{}", name, std::process::id()));
+  fs::remove_dir_all(&dir).ok();
+  dir
+}
+
+#[test]
+fn test_snapshot_poll() {
+  let dir = temp_dir("snapshot-poll");
+  let store = SnapshotStore::new(&dir);
+
+  let l1 = vec![item(1, "a"), item(2, "a")];
+  let changes = store.poll::<i32, _>("orders", &l1).unwrap();
+  assert_eq!(changes.add.len(), 2);
+  assert!(changes.update.is_empty() && changes.delete.is_empty());
+  assert_eq!(store.load::<Item>("orders").unwrap(), Some(l1.clone()));
+
+  let l2 = vec![item(2, "b"), item(3, "a")];
+  let changes = store.diff::<i32, _>("orders", &l2).unwrap();
+  assert_eq!(changes.add[0].item, l2[1]);
+  assert_eq!(changes.update[0].existing, l1[1]);
+  assert_eq!(changes.delete[0].item, l1[0]);
+  // `diff` doesn't move the snapshot forward
+  assert_eq!(store.load::<Item>("orders").unwrap(), Some(l1));
+
+  store.poll::<i32, _>("orders", &l2).unwrap();
+  assert!(store.poll::<i32, _>("orders", &l2).unwrap().add.is_empty());
+
+  // sources are independent
+  assert_eq!(store.load::<Item>("listings").unwrap(), None);
+  store.remove("orders").unwrap();
+  assert_eq!(store.load::<Item>("orders").unwrap(), None);
+  fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_snapshot_errors() {
+  let dir = temp_dir("snapshot-errors");
+  let store = SnapshotStore::new(&dir);
+
+  match store.save::<Item>("../orders", &[]) {
+    Err(SnapshotError::InvalidSource(source)) => assert_eq!(source, "../orders"),
+    other => panic!("unexpected {:?}", other),
+  }
+
+  store.save::<Item>("orders", &[]).unwrap();
+  let path = dir.join("orders.snapshot.json");
+  assert_eq!(
+    fs::read_to_string(&path).unwrap(),
+    r#"{"version":1,"items":[]}"#
+  );
+
+  fs::write(&path, r#"{"version":2,"items":[]}"#).unwrap();
+  match store.load::<Item>("orders") {
+    Err(SnapshotError::UnsupportedVersion(2)) => {}
+    other => panic!("unexpected {:?}", other),
+  }
+
+  fs::write(&path, "{").unwrap();
+  match store.load::<Item>("orders") {
+    Err(SnapshotError::Json(_)) => {}
+    other => panic!("unexpected {:?}", other),
+  }
+  fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,89 @@
+use s2_utils::list::{
+  get_changed_items, get_sorted_changes, ListSide, OutOfOrderError, SortedChange,
+};
+
+use crate::support::dedup_deletes;
+
+fn sorted_changes(l1: &[i32], l2: &[i32]) -> Vec<SortedChange<i32, i32>> {
+  get_sorted_changes(l1.iter().cloned(), l2.iter().cloned())
+    .collect::<Result<_, _>>()
+    .unwrap()
+}
+
+#[test]
+fn test_get_sorted_changes() {
+  use s2_utils::list::SortedChange::*;
+
+  assert_eq!(
+    sorted_changes(&[1, 2, 4, 6], &[2, 3, 4, 5]),
+    vec![
+      Delete(1),
+      Update(2, 2),
+      Add(3),
+      Update(4, 4),
+      Add(5),
+      Delete(6)
+    ]
+  );
+  assert_eq!(sorted_changes(&[], &[1, 2]), vec![Add(1), Add(2)]);
+  assert_eq!(sorted_changes(&[1, 2], &[]), vec![Delete(1), Delete(2)]);
+}
+
+#[test]
+fn test_sorted_changes_same_as_get_changed_items() {
+  let cases: Vec<(Vec<i32>, Vec<i32>)> = vec![
+    (vec![1, 2, 2, 3], vec![1, 3]),
+    (vec![1, 2, 2, 3], vec![1, 2, 3]),
+    (vec![1, 2, 2, 3], vec![1, 3, 3]),
+    (vec![1, 2, 2, 3], vec![1, 2, 2, 3, 4, 4]),
+    (vec![1, 1, 1], vec![1, 1]),
+  ];
+
+  for (l1, l2) in &cases {
+    let mut expected = get_changed_items(l1, l2);
+    dedup_deletes(&mut expected.delete);
+    let mut add = vec![];
+    let mut update = vec![];
+    let mut delete = vec![];
+    for change in sorted_changes(l1, l2) {
+      match change {
+        SortedChange::Add(n) => add.push(n),
+        SortedChange::Update(e, n) => update.push((e, n)),
+        SortedChange::Delete(e) => delete.push(e),
+      }
+    }
+    delete.sort();
+    let mut expected_delete: Vec<i32> = expected.delete.iter().map(|e| **e).collect();
+    expected_delete.sort();
+
+    assert_eq!(add, expected.add.iter().map(|n| **n).collect::<Vec<_>>());
+    assert_eq!(
+      update,
+      expected
+        .update
+        .iter()
+        .map(|&(e, n)| (*e, *n))
+        .collect::<Vec<_>>()
+    );
+    assert_eq!(delete, expected_delete);
+  }
+}
+
+#[test]
+fn test_sorted_changes_out_of_order() {
+  let changes: Vec<_> = get_sorted_changes(vec![1, 3, 2], vec![1, 2, 3]).collect();
+  assert_eq!(
+    changes,
+    vec![
+      Ok(SortedChange::Update(1, 1)),
+      Ok(SortedChange::Add(2)),
+      Ok(SortedChange::Update(3, 3)),
+      Err(OutOfOrderError {
+        side: ListSide::Existing,
+        index: 2,
+        key: 2,
+        previous_key: 3,
+      }),
+    ]
+  );
+}
@@ -0,0 +1,51 @@
+// Fixtures and helpers shared by the test modules.
+
+use s2_utils_derive::HasItemKey;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, HasItemKey, Serialize, Deserialize)]
+pub struct Item {
+  #[item_key]
+  pub id: i32,
+  pub name: String,
+}
+
+pub fn item(id: i32, name: &str) -> Item {
+  Item {
+    id,
+    name: name.to_string(),
+  }
+}
+
+// deterministic pseudo random numbers, a 64 bit LCG
+pub struct Lcg(u64);
+
+impl Lcg {
+  pub fn new(seed: u64) -> Self {
+    Lcg(seed)
+  }
+
+  pub fn next(&mut self) -> u64 {
+    self.0 = self
+      .0
+      .wrapping_mul(6_364_136_223_846_793_005)
+      .wrapping_add(1_442_695_040_888_963_407);
+    self.0
+  }
+
+  // number in `0..n`, from the high bits
+  pub fn below(&mut self, n: usize) -> usize {
+    (self.next() >> 33) as usize % n
+  }
+}
+
+// `get_changed_items` deletes the extra existing items of a key once per new
+// item with the key, keeps the first delete of each item
+pub fn dedup_deletes<T>(delete: &mut Vec<&T>) {
+  let mut seen: Vec<*const T> = vec![];
+  delete.retain(|&ei| {
+    let first = !seen.contains(&(ei as *const T));
+    seen.push(ei);
+    first
+  });
+}
@@ -0,0 +1,115 @@
+use s2_utils::list::{
+  try_get_changed_items, try_get_dup_items, try_group_by_key, MissingItemKey, TryHasItemKey,
+};
+use s2_utils_derive::TryHasItemKey;
+
+#[derive(Debug, PartialEq, TryHasItemKey)]
+struct Listing {
+  #[item_key]
+  sku: Option<String>,
+  qty: i32,
+}
+
+#[derive(Debug, PartialEq, TryHasItemKey)]
+#[try_has_item_key(i64, error = "std::num::ParseIntError", expr = "self.id.parse()")]
+struct Row {
+  id: String,
+}
+
+#[derive(Debug, PartialEq, TryHasItemKey)]
+struct Line(#[item_key] i32, #[item_key] Option<&'static str>);
+
+fn listing(sku: Option<&str>, qty: i32) -> Listing {
+  Listing {
+    sku: sku.map(String::from),
+    qty,
+  }
+}
+
+#[test]
+fn test_derive_try_has_item_key() {
+  assert_eq!(
+    listing(Some("a"), 1).try_get_item_key(),
+    Ok("a".to_string())
+  );
+  assert_eq!(
+    listing(None, 1).try_get_item_key(),
+    Err(MissingItemKey { field: "sku" })
+  );
+  assert_eq!(Line(1, Some("a")).try_get_item_key(), Ok((1, "a")));
+  assert_eq!(
+    Line(1, None).try_get_item_key(),
+    Err(MissingItemKey { field: "1" })
+  );
+
+  let row = Row {
+    id: "x".to_string(),
+  };
+  assert!(row.try_get_item_key().is_err());
+}
+
+#[test]
+fn test_try_get_changed_items() {
+  let existing = vec![
+    listing(Some("a"), 1),
+    listing(None, 2),
+    listing(Some("b"), 3),
+  ];
+  let new = vec![
+    listing(Some("b"), 4),
+    listing(Some("c"), 5),
+    listing(None, 6),
+  ];
+  let changes = try_get_changed_items(&existing, &new);
+  assert!(changes.has_invalid());
+  assert_eq!(changes.changes.add, vec![&new[1]]);
+  assert_eq!(changes.changes.update, vec![(&existing[2], &new[0])]);
+  assert_eq!(changes.changes.delete, vec![&existing[0]]);
+  assert_eq!(changes.invalid_existing.len(), 1);
+  assert_eq!(changes.invalid_existing[0].index, 1);
+  assert_eq!(changes.invalid_new[0].item, &new[2]);
+  assert_eq!(
+    changes.invalid_new[0].error.to_string(),
+    "item key field `sku` is missing"
+  );
+
+  let rows = vec![
+    Row {
+      id: "1".to_string(),
+    },
+    Row {
+      id: "?".to_string(),
+    },
+  ];
+  let changes = try_get_changed_items::<i64, _, _>(&rows, &rows[..1]);
+  assert_eq!(changes.changes.update, vec![(&rows[0], &rows[0])]);
+  assert_eq!(changes.invalid_existing[0].index, 1);
+  assert!(changes.invalid_new.is_empty());
+}
+
+#[test]
+fn test_try_dup_and_group() {
+  let items = vec![
+    listing(Some("a"), 1),
+    listing(None, 2),
+    listing(Some("b"), 3),
+    listing(Some("a"), 4),
+  ];
+
+  let (dups, invalid) = try_get_dup_items::<String, _>(&items);
+  assert_eq!(
+    dups,
+    vec![("a".to_string(), vec![(0, &items[0]), (3, &items[3])])]
+  );
+  assert_eq!(invalid.len(), 1);
+
+  let (groups, invalid) = try_group_by_key::<String, _>(&items);
+  assert_eq!(
+    groups,
+    vec![
+      ("a".to_string(), vec![&items[0], &items[3]]),
+      ("b".to_string(), vec![&items[2]]),
+    ]
+  );
+  assert_eq!(invalid[0].index, 1);
+}
@@ -17,6 +17,11 @@ pub struct Delegate<A, R> {
   max: RwLock<i64>,
 }
 
+#[allow(
+  clippy::new_without_default,
+  clippy::len_without_is_empty,
+  clippy::match_like_matches_macro
+)]
 impl<A, R> Delegate<A, R> {
   pub fn new() -> Self {
     Delegate {
@@ -0,0 +1,526 @@
+//! Grouping of items by key: hash groups, nested groups, pivots and runs of
+//! sorted input.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+use std::ops::Add;
+use std::vec::IntoIter;
+
+use crate::key_index::{group_keyed, KeyIndex};
+
+/// Groups items by key, in the order each key is first seen.
+pub trait GroupByKey<T, I>: Sized + IntoIterator<Item = T> {
+  fn group_by_key<F>(self, f: F) -> IntoIter<(I, Vec<T>)>
+  where
+    I: Hash + Eq,
+    F: Fn(&T) -> I,
+  {
+    group_keyed(self.into_iter().map(|item| {
+      let id = f(&item);
+      (item, id)
+    }))
+    .into_iter()
+  }
+
+  fn group_by_key_map<F>(self, f: F) -> HashMap<I, Vec<T>>
+  where
+    I: Hash + Eq,
+    F: Fn(&T) -> I,
+  {
+    let mut groups: HashMap<I, Vec<T>> = HashMap::new();
+    for item in self.into_iter() {
+      groups.entry(f(&item)).or_default().push(item);
+    }
+    groups
+  }
+
+  /// Same as `group_by_key_map`, sorted by key.
+  fn group_by_key_sorted<F>(self, f: F) -> BTreeMap<I, Vec<T>>
+  where
+    I: Ord,
+    F: Fn(&T) -> I,
+  {
+    let mut groups: BTreeMap<I, Vec<T>> = BTreeMap::new();
+    for item in self.into_iter() {
+      groups.entry(f(&item)).or_default().push(item);
+    }
+    groups
+  }
+
+  /// Folds the items of each group, starting from the value returned by `init`.
+  fn fold_by_key<F, A, N, G>(self, f: F, init: N, mut fold: G) -> IntoIter<(I, A)>
+  where
+    I: Hash + Eq,
+    F: Fn(&T) -> I,
+    N: Fn() -> A,
+    G: FnMut(A, T) -> A,
+  {
+    let mut index = KeyIndex::new();
+    let mut values: Vec<Option<A>> = vec![];
+    for item in self.into_iter() {
+      let pos = index.insert(f(&item));
+      let value = match values.get_mut(pos) {
+        Some(value) => value.take().unwrap(),
+        None => {
+          values.push(None);
+          init()
+        }
+      };
+      values[pos] = Some(fold(value, item));
+    }
+
+    index
+      .into_keys()
+      .into_iter()
+      .zip(values)
+      .map(|(id, value)| (id, value.unwrap()))
+      .collect::<Vec<_>>()
+      .into_iter()
+  }
+
+  fn count_by_key<F>(self, f: F) -> IntoIter<(I, usize)>
+  where
+    I: Hash + Eq,
+    F: Fn(&T) -> I,
+  {
+    self.fold_by_key(f, || 0, |count, _| count + 1)
+  }
+
+  fn sum_by_key<F, S, V>(self, f: F, v: V) -> IntoIter<(I, S)>
+  where
+    I: Hash + Eq,
+    F: Fn(&T) -> I,
+    S: Add<Output = S> + Default,
+    V: Fn(&T) -> S,
+  {
+    self.fold_by_key(f, S::default, |sum, item| sum + v(&item))
+  }
+
+  /// Returns the minimum item of each group. If several items are equally
+  /// minimum, the first one is returned.
+  fn min_per_key_by<F, C>(self, f: F, compare: C) -> IntoIter<(I, T)>
+  where
+    I: Hash + Eq,
+    F: Fn(&T) -> I,
+    C: Fn(&T, &T) -> Ordering,
+  {
+    select_per_key(self, f, |item, min| compare(item, min) == Ordering::Less)
+  }
+
+  /// Returns the maximum item of each group. If several items are equally
+  /// maximum, the last one is returned.
+  fn max_per_key_by<F, C>(self, f: F, compare: C) -> IntoIter<(I, T)>
+  where
+    I: Hash + Eq,
+    F: Fn(&T) -> I,
+    C: Fn(&T, &T) -> Ordering,
+  {
+    select_per_key(self, f, |item, max| compare(item, max) != Ordering::Less)
+  }
+
+  /// Groups items by several keys, one nesting level per key closure. The
+  /// closures are passed as a tuple and may return different key types, e.g.
+  /// `(|s: &Stock| s.warehouse, |s: &Stock| s.day)`.
+  fn group_by_keys<G>(self, keys: G) -> G::Output
+  where
+    G: GroupKeys<T, Key = I>,
+  {
+    keys.group(self.into_iter().collect())
+  }
+
+  /// Aggregates the items of each row/column pair into a matrix. Rows and
+  /// columns are in the order they are first seen.
+  fn pivot<C, FR, FC, V, A>(self, row: FR, column: FC, mut aggregate: A) -> PivotTable<I, C, V>
+  where
+    I: Hash + Eq,
+    C: Hash + Eq,
+    FR: Fn(&T) -> I,
+    FC: Fn(&T) -> C,
+    A: FnMut(Vec<T>) -> V,
+  {
+    let mut rows = KeyIndex::new();
+    let mut columns = KeyIndex::new();
+    let mut cells: Vec<Vec<Vec<T>>> = vec![];
+    for item in self.into_iter() {
+      let r = rows.insert(row(&item));
+      if r == cells.len() {
+        cells.push(vec![]);
+      }
+      let c = columns.insert(column(&item));
+      if cells[r].len() <= c {
+        cells[r].resize_with(c + 1, Vec::new);
+      }
+      cells[r][c].push(item);
+    }
+
+    let rows = rows.into_keys();
+    let columns = columns.into_keys();
+    let width = columns.len();
+    PivotTable {
+      rows,
+      columns,
+      cells: cells
+        .into_iter()
+        .map(|row| {
+          let mut values: Vec<_> = row
+            .into_iter()
+            .map(|items| {
+              if items.is_empty() {
+                None
+              } else {
+                Some(aggregate(items))
+              }
+            })
+            .collect();
+          values.resize_with(width, || None);
+          values
+        })
+        .collect(),
+    }
+  }
+}
+
+// Keeps 1 item per group, `replace(item, current)` decides if `item` replaces
+// the current one.
+fn select_per_key<G, T, I, F, R>(items: G, f: F, replace: R) -> IntoIter<(I, T)>
+where
+  G: GroupByKey<T, I>,
+  I: Hash + Eq,
+  F: Fn(&T) -> I,
+  R: Fn(&T, &T) -> bool,
+{
+  items
+    .fold_by_key(
+      f,
+      || None,
+      |current, item| match current {
+        Some(current) if !replace(&item, &current) => Some(current),
+        _ => Some(item),
+      },
+    )
+    .map(|(id, item)| (id, item.unwrap()))
+    .collect::<Vec<_>>()
+    .into_iter()
+}
+
+impl<T, Item, Id> GroupByKey<Item, Id> for T where T: IntoIterator<Item = Item> {}
+
+/// Tuple of 1 to 4 key closures for `group_by_keys`. Each level is a
+/// `Vec` of `(key, next level)` in first-seen order, the last level holds
+/// the items.
+pub trait GroupKeys<T> {
+  /// Key of the first level.
+  type Key;
+  type Output;
+
+  fn group(&self, items: Vec<T>) -> Self::Output;
+}
+
+impl<T, K1, F1> GroupKeys<T> for (F1,)
+where
+  K1: Hash + Eq,
+  F1: Fn(&T) -> K1,
+{
+  type Key = K1;
+  type Output = Vec<(K1, Vec<T>)>;
+
+  fn group(&self, items: Vec<T>) -> Self::Output {
+    items.group_by_key(&self.0).collect()
+  }
+}
+
+impl<T, K1, K2, F1, F2> GroupKeys<T> for (F1, F2)
+where
+  K1: Hash + Eq,
+  K2: Hash + Eq,
+  F1: Fn(&T) -> K1,
+  F2: Fn(&T) -> K2,
+{
+  type Key = K1;
+  type Output = Vec<(K1, Vec<(K2, Vec<T>)>)>;
+
+  fn group(&self, items: Vec<T>) -> Self::Output {
+    items
+      .group_by_key(&self.0)
+      .map(|(key, items)| (key, (&self.1,).group(items)))
+      .collect()
+  }
+}
+
+impl<T, K1, K2, K3, F1, F2, F3> GroupKeys<T> for (F1, F2, F3)
+where
+  K1: Hash + Eq,
+  K2: Hash + Eq,
+  K3: Hash + Eq,
+  F1: Fn(&T) -> K1,
+  F2: Fn(&T) -> K2,
+  F3: Fn(&T) -> K3,
+{
+  type Key = K1;
+  type Output = Vec<(K1, Vec<(K2, Vec<(K3, Vec<T>)>)>)>;
+
+  fn group(&self, items: Vec<T>) -> Self::Output {
+    items
+      .group_by_key(&self.0)
+      .map(|(key, items)| (key, (&self.1, &self.2).group(items)))
+      .collect()
+  }
+}
+
+impl<T, K1, K2, K3, K4, F1, F2, F3, F4> GroupKeys<T> for (F1, F2, F3, F4)
+where
+  K1: Hash + Eq,
+  K2: Hash + Eq,
+  K3: Hash + Eq,
+  K4: Hash + Eq,
+  F1: Fn(&T) -> K1,
+  F2: Fn(&T) -> K2,
+  F3: Fn(&T) -> K3,
+  F4: Fn(&T) -> K4,
+{
+  type Key = K1;
+  type Output = Vec<(K1, Vec<(K2, Vec<(K3, Vec<(K4, Vec<T>)>)>)>)>;
+
+  fn group(&self, items: Vec<T>) -> Self::Output {
+    items
+      .group_by_key(&self.0)
+      .map(|(key, items)| (key, (&self.1, &self.2, &self.3).group(items)))
+      .collect()
+  }
+}
+
+/// Result of `pivot`. `cells[r][c]` is the aggregated value of `rows[r]` and
+/// `columns[c]`, `None` if no item has both keys.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PivotTable<R, C, V> {
+  pub rows: Vec<R>,
+  pub columns: Vec<C>,
+  pub cells: Vec<Vec<Option<V>>>,
+}
+
+impl<R, C, V> PivotTable<R, C, V>
+where
+  R: PartialEq,
+  C: PartialEq,
+{
+  pub fn get(&self, row: &R, column: &C) -> Option<&V> {
+    let r = self.rows.iter().position(|id| id == row)?;
+    let c = self.columns.iter().position(|id| id == column)?;
+    self.cells[r][c].as_ref()
+  }
+}
+
+/// Groups consecutive items with the same key, without buffering more than
+/// one group. Meant for inputs already sorted by key, e.g. DB cursors.
+pub trait GroupRunsByKey: Iterator + Sized {
+  fn group_runs_by_key<K, F>(self, f: F) -> GroupRuns<Self, K, F>
+  where
+    K: PartialEq,
+    F: FnMut(&Self::Item) -> K,
+  {
+    GroupRuns {
+      iter: self,
+      f,
+      next: None,
+      consumed: 0,
+    }
+  }
+}
+
+impl<I: Iterator> GroupRunsByKey for I {}
+
+/// Iterator returned by `group_runs_by_key`, yields `(key, items)` for each
+/// run of consecutive items with the same key.
+pub struct GroupRuns<I, K, F>
+where
+  I: Iterator,
+{
+  iter: I,
+  f: F,
+  // first item of the next run
+  next: Option<(K, I::Item)>,
+  // number of items in the yielded runs
+  consumed: usize,
+}
+
+impl<I, K, F> GroupRuns<I, K, F>
+where
+  I: Iterator,
+  K: PartialEq,
+  F: FnMut(&I::Item) -> K,
+{
+  /// Fails if a key shows up again after its run ended, i.e. the input is not
+  /// grouped by key. The key of every run is kept in a `HashSet`.
+  pub fn check_runs(self) -> CheckedGroupRuns<I, K, F>
+  where
+    K: Hash + Eq + Clone,
+  {
+    CheckedGroupRuns {
+      runs: self,
+      seen: HashSet::new(),
+      failed: false,
+    }
+  }
+
+  /// Fails if a run's key is smaller than the previous key, i.e. the input is
+  /// not sorted by key. Only the last key is kept, so streaming input is
+  /// checked in constant memory.
+  pub fn check_sorted_runs(self) -> SortedGroupRuns<I, K, F>
+  where
+    K: Ord + Clone,
+  {
+    SortedGroupRuns {
+      runs: self,
+      last: None,
+      failed: false,
+    }
+  }
+
+  fn pull(&mut self) -> Option<(K, I::Item)> {
+    let f = &mut self.f;
+    self.iter.next().map(|item| (f(&item), item))
+  }
+}
+
+impl<I, K, F> Iterator for GroupRuns<I, K, F>
+where
+  I: Iterator,
+  K: PartialEq,
+  F: FnMut(&I::Item) -> K,
+{
+  type Item = (K, Vec<I::Item>);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let (key, first) = match self.next.take() {
+      Some(next) => next,
+      None => self.pull()?,
+    };
+    let mut group = vec![first];
+    while let Some((next_key, item)) = self.pull() {
+      if next_key == key {
+        group.push(item);
+      } else {
+        self.next = Some((next_key, item));
+        break;
+      }
+    }
+    self.consumed += group.len();
+    Some((key, group))
+  }
+}
+
+/// Iterator returned by `GroupRuns::check_runs`, stops after the first error.
+pub struct CheckedGroupRuns<I, K, F>
+where
+  I: Iterator,
+{
+  runs: GroupRuns<I, K, F>,
+  seen: HashSet<K>,
+  failed: bool,
+}
+
+impl<I, K, F> Iterator for CheckedGroupRuns<I, K, F>
+where
+  I: Iterator,
+  K: Hash + Eq + Clone,
+  F: FnMut(&I::Item) -> K,
+{
+  type Item = Result<(K, Vec<I::Item>), RepeatedKeyError<K>>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.failed {
+      return None;
+    }
+    let index = self.runs.consumed;
+    let (key, group) = self.runs.next()?;
+    if !self.seen.insert(key.clone()) {
+      self.failed = true;
+      return Some(Err(RepeatedKeyError { key, index }));
+    }
+    Some(Ok((key, group)))
+  }
+}
+
+/// A key showed up again after its run ended.
+#[derive(Debug, PartialEq)]
+pub struct RepeatedKeyError<K> {
+  pub key: K,
+  /// Index of the first item of the repeated run.
+  pub index: usize,
+}
+
+impl<K: fmt::Debug> fmt::Display for RepeatedKeyError<K> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "key {:?} at index {} repeated after its run ended",
+      self.key, self.index
+    )
+  }
+}
+
+impl<K: fmt::Debug> std::error::Error for RepeatedKeyError<K> {}
+
+/// Iterator returned by `GroupRuns::check_sorted_runs`, stops after the first
+/// error.
+pub struct SortedGroupRuns<I, K, F>
+where
+  I: Iterator,
+{
+  runs: GroupRuns<I, K, F>,
+  // key of the previous run
+  last: Option<K>,
+  failed: bool,
+}
+
+impl<I, K, F> Iterator for SortedGroupRuns<I, K, F>
+where
+  I: Iterator,
+  K: Ord + Clone,
+  F: FnMut(&I::Item) -> K,
+{
+  type Item = Result<(K, Vec<I::Item>), UnsortedRunError<K>>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.failed {
+      return None;
+    }
+    let index = self.runs.consumed;
+    let (key, group) = self.runs.next()?;
+    if let Some(previous_key) = self.last.take() {
+      if key < previous_key {
+        self.failed = true;
+        return Some(Err(UnsortedRunError {
+          key,
+          previous_key,
+          index,
+        }));
+      }
+    }
+    self.last = Some(key.clone());
+    Some(Ok((key, group)))
+  }
+}
+
+/// A run's key is smaller than the key of the run before it.
+#[derive(Debug, PartialEq)]
+pub struct UnsortedRunError<K> {
+  pub key: K,
+  pub previous_key: K,
+  /// Index of the first item of the run.
+  pub index: usize,
+}
+
+impl<K: fmt::Debug> fmt::Display for UnsortedRunError<K> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "key {:?} at index {} is not sorted after {:?}",
+      self.key, self.index, self.previous_key
+    )
+  }
+}
+
+impl<K: fmt::Debug> std::error::Error for UnsortedRunError<K> {}
@@ -0,0 +1,61 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Positions of keys in the order they are first seen. Lets grouping code
+/// keep its groups in a `Vec` and get the keys back without cloning them.
+pub(crate) struct KeyIndex<K> {
+  positions: HashMap<K, usize>,
+}
+
+impl<K> KeyIndex<K>
+where
+  K: Hash + Eq,
+{
+  pub(crate) fn new() -> Self {
+    KeyIndex {
+      positions: HashMap::new(),
+    }
+  }
+
+  /// Returns the position of `key`, a new key gets the next position.
+  pub(crate) fn insert(&mut self, key: K) -> usize {
+    let next = self.positions.len();
+    *self.positions.entry(key).or_insert(next)
+  }
+
+  pub(crate) fn get<Q>(&self, key: &Q) -> Option<usize>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    self.positions.get(key).cloned()
+  }
+
+  /// Moves the keys out, in position order.
+  pub(crate) fn into_keys(self) -> Vec<K> {
+    let mut keys: Vec<Option<K>> = self.positions.iter().map(|_| None).collect();
+    for (key, pos) in self.positions {
+      keys[pos] = Some(key);
+    }
+    keys.into_iter().map(Option::unwrap).collect()
+  }
+}
+
+/// Groups items with their key already extracted, in the order each key is
+/// first seen.
+pub(crate) fn group_keyed<T, K>(items: impl IntoIterator<Item = (T, K)>) -> Vec<(K, Vec<T>)>
+where
+  K: Hash + Eq,
+{
+  let mut index = KeyIndex::new();
+  let mut groups: Vec<Vec<T>> = vec![];
+  for (item, key) in items {
+    let pos = index.insert(key);
+    if pos == groups.len() {
+      groups.push(vec![]);
+    }
+    groups[pos].push(item);
+  }
+  index.into_keys().into_iter().zip(groups).collect()
+}
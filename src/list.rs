@@ -1,5 +1,45 @@
+use std::hash::Hash;
+
+use crate::key_index::KeyIndex;
+
 pub use super::GroupByKey;
 
+mod batch;
+mod by_key;
+mod changeset;
+mod duplicate;
+mod join;
+mod key_matcher;
+mod key_ref;
+mod keyed_vec;
+mod merge3;
+mod nested;
+mod normalize;
+mod ordered;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod reconcile;
+mod sorted;
+mod try_key;
+
+pub use self::batch::*;
+pub use self::by_key::*;
+pub use self::changeset::*;
+pub use self::duplicate::*;
+pub use self::join::*;
+pub use self::key_matcher::*;
+pub use self::key_ref::*;
+pub use self::keyed_vec::*;
+pub use self::merge3::*;
+pub use self::nested::*;
+pub use self::normalize::*;
+pub use self::ordered::*;
+#[cfg(feature = "rayon")]
+pub use self::parallel::*;
+pub use self::reconcile::*;
+pub use self::sorted::*;
+pub use self::try_key::*;
+
 pub trait HasItemKey<K> {
   fn get_item_key(&self) -> K;
 }
@@ -13,7 +53,7 @@ where
   }
 }
 
-impl<'a, T, K> HasItemKey<K> for &'a T
+impl<T, K> HasItemKey<K> for &T
 where
   T: HasItemKey<K>,
 {
@@ -46,6 +86,13 @@ macro_rules! impl_has_item_key {
   };
 }
 
+/// Which list of a diff an item comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListSide {
+  Existing,
+  New,
+}
+
 pub struct ChangedItems<'a, TE, TN>
 where
   TE: 'a,
@@ -56,14 +103,17 @@ where
   pub delete: Vec<&'a TE>,
 }
 
+/// Matches the items by key. Of the existing items sharing the key of a new
+/// item, the first one is updated and the rest are deleted, again for every
+/// new item with that key.
 pub fn get_changed_items<'a, K, TE, TN>(
-  existing_items: &'a Vec<TE>,
-  new_items: &'a Vec<TN>,
+  existing_items: &'a [TE],
+  new_items: &'a [TN],
 ) -> ChangedItems<'a, TE, TN>
 where
   K: PartialEq,
-  TE: HasItemKey<K> + 'a,
-  TN: HasItemKey<K> + 'a,
+  &'a TE: HasItemKey<K>,
+  &'a TN: HasItemKey<K>,
 {
   let mut result = ChangedItems {
     add: vec![],
@@ -71,44 +121,34 @@ where
     delete: vec![],
   };
 
+  // extract each key once, the loops below only compare them
+  let existing: Vec<(K, &TE)> = existing_items
+    .iter()
+    .map(|ei| (ei.get_item_key(), ei))
+    .collect();
+  let new: Vec<(K, &TN)> = new_items.iter().map(|ni| (ni.get_item_key(), ni)).collect();
+
   let mut update = vec![];
 
-  for ni in new_items {
-    let key = ni.get_item_key();
-    let mut existing: Vec<_> = existing_items
-      .iter()
-      .filter(|i| i.get_item_key() == key)
-      .collect();
-    // If more than 1 matches found, update the first one, remove the rest.
-    let existing = if existing.len() > 1 {
-      let item = existing.remove(0);
-      result.delete.extend(existing);
-      Some(item)
-    } else {
-      if existing.len() == 1 {
-        Some(existing.remove(0))
-      } else {
-        None
-      }
-    };
-    match existing {
+  for (key, ni) in &new {
+    let mut existing = existing.iter().filter(|(k, _)| k == key).map(|(_, ei)| *ei);
+    match existing.next() {
       Some(ei) => {
+        // If more than 1 matches found, update the first one, remove the rest.
+        result.delete.extend(existing);
         if let Some(pos) = update.iter().position(|(k, _, _)| *k == key) {
-          update[pos] = (key, ei, ni);
+          update[pos] = (key, ei, *ni);
         } else {
-          update.push((key, ei, ni));
+          update.push((key, ei, *ni));
         }
       }
-      None => result.add.push(ni),
+      None => result.add.push(*ni),
     }
   }
 
-  for ei in existing_items {
-    let key = ei.get_item_key();
-    let new = new_items.iter().find(|i| i.get_item_key() == key);
-    match new {
-      Some(_) => {}
-      None => result.delete.push(ei),
+  for (key, ei) in &existing {
+    if !new.iter().any(|(k, _)| k == key) {
+      result.delete.push(*ei);
     }
   }
 
@@ -117,14 +157,189 @@ where
   result
 }
 
-pub fn get_dup_items<'a, K, T>(items: &'a Vec<T>) -> Vec<(K, Vec<(usize, &'a T)>)>
+/// Same as `get_changed_items`, but indexes the keys in a `HashMap`.
+///
+/// Each key is extracted exactly once and the result is identical to
+/// `get_changed_items`, including the handling of duplicated keys.
+pub fn get_changed_items_hashed<'a, K, TE, TN>(
+  existing_items: &'a [TE],
+  new_items: &'a [TN],
+) -> ChangedItems<'a, TE, TN>
+where
+  K: Hash + Eq,
+  &'a TE: HasItemKey<K>,
+  &'a TN: HasItemKey<K>,
+{
+  hashed_changed_items(
+    existing_items,
+    new_items,
+    |ei| ei.get_item_key(),
+    |ni| ni.get_item_key(),
+  )
+}
+
+pub(crate) fn hashed_changed_items<'a, K, TE, TN, IE, IN, FE, FN>(
+  existing_items: IE,
+  new_items: IN,
+  existing_key: FE,
+  new_key: FN,
+) -> ChangedItems<'a, TE, TN>
+where
+  K: Hash + Eq,
+  TE: 'a,
+  TN: 'a,
+  IE: IntoIterator<Item = &'a TE>,
+  IN: IntoIterator<Item = &'a TN>,
+  FE: Fn(&'a TE) -> K,
+  FN: Fn(&'a TN) -> K,
+{
+  keyed_changed_items(
+    existing_items.into_iter().map(|ei| (ei, existing_key(ei))),
+    new_items.into_iter().map(|ni| (ni, new_key(ni))),
+  )
+}
+
+// Same as `hashed_changed_items`, with the keys already extracted.
+pub(crate) fn keyed_changed_items<'a, K, TE, TN, IE, IN>(
+  existing_items: IE,
+  new_items: IN,
+) -> ChangedItems<'a, TE, TN>
+where
+  K: Hash + Eq,
+  TE: 'a,
+  TN: 'a,
+  IE: IntoIterator<Item = (&'a TE, K)>,
+  IN: IntoIterator<Item = (&'a TN, K)>,
+{
+  // existing items sharing the same key, and the position of their entry in `update`
+  struct Group<'a, TE> {
+    items: Vec<&'a TE>,
+    update: Option<usize>,
+  }
+
+  let mut index = KeyIndex::new();
+  let mut groups: Vec<Group<TE>> = vec![];
+  let mut existing = vec![];
+  for (ei, key) in existing_items {
+    let group = index.insert(key);
+    if group == groups.len() {
+      groups.push(Group {
+        items: vec![],
+        update: None,
+      });
+    }
+    groups[group].items.push(ei);
+    existing.push((ei, group));
+  }
+
+  let mut result = ChangedItems {
+    add: vec![],
+    update: vec![],
+    delete: vec![],
+  };
+
+  for (ni, key) in new_items {
+    match index.get(&key) {
+      Some(group) => {
+        let group = &mut groups[group];
+        // If more than 1 matches found, update the first one, remove the rest.
+        result.delete.extend(group.items.iter().skip(1));
+        match group.update {
+          Some(pos) => result.update[pos].1 = ni,
+          None => {
+            group.update = Some(result.update.len());
+            result.update.push((group.items[0], ni));
+          }
+        }
+      }
+      None => result.add.push(ni),
+    }
+  }
+
+  for (ei, group) in existing {
+    if groups[group].update.is_none() {
+      result.delete.push(ei);
+    }
+  }
+
+  result
+}
+
+/// Compares two items field by field.
+///
+/// Can be derived with `#[derive(DiffFields)]`, fields marked with
+/// `#[diff_fields(skip)]` are excluded from the comparison.
+pub trait DiffFields<Rhs = Self> {
+  /// Returns the names of the fields that differ.
+  fn diff_fields(&self, other: &Rhs) -> Vec<&'static str>;
+}
+
+/// `ChangedItems` with the matched items split into real updates and no-ops.
+pub struct FieldChangedItems<'a, TE, TN>
+where
+  TE: 'a,
+  TN: 'a,
+{
+  pub add: Vec<&'a TN>,
+  pub update: Vec<(&'a TE, &'a TN, Vec<&'static str>)>,
+  pub unchanged: Vec<(&'a TE, &'a TN)>,
+  pub delete: Vec<&'a TE>,
+}
+
+impl<'a, TE, TN> ChangedItems<'a, TE, TN> {
+  /// Moves the matches without any differing field to `unchanged`.
+  pub fn split_unchanged(self) -> FieldChangedItems<'a, TE, TN>
+  where
+    TE: DiffFields<TN>,
+  {
+    let mut update = vec![];
+    let mut unchanged = vec![];
+    for (ei, ni) in self.update {
+      let fields = ei.diff_fields(ni);
+      if fields.is_empty() {
+        unchanged.push((ei, ni));
+      } else {
+        update.push((ei, ni, fields));
+      }
+    }
+    FieldChangedItems {
+      add: self.add,
+      update,
+      unchanged,
+      delete: self.delete,
+    }
+  }
+}
+
+pub fn get_field_changed_items<'a, K, TE, TN>(
+  existing_items: &'a [TE],
+  new_items: &'a [TN],
+) -> FieldChangedItems<'a, TE, TN>
+where
+  K: PartialEq,
+  TE: DiffFields<TN>,
+  &'a TE: HasItemKey<K>,
+  &'a TN: HasItemKey<K>,
+{
+  get_changed_items(existing_items, new_items).split_unchanged()
+}
+
+pub fn get_dup_items<'a, K, T>(items: &'a [T]) -> Vec<(K, Vec<(usize, &'a T)>)>
 where
   K: PartialEq,
-  T: HasItemKey<K> + 'a,
+  &'a T: HasItemKey<K>,
+{
+  dup_items_by(items, |i| i.get_item_key())
+}
+
+pub(crate) fn dup_items_by<'a, K, T, F>(items: &'a [T], key: F) -> Vec<(K, Vec<(usize, &'a T)>)>
+where
+  K: PartialEq,
+  F: Fn(&'a T) -> K,
 {
   let mut key_items: Vec<(K, Vec<usize>)> = vec![];
   for (idx, item) in items.iter().enumerate() {
-    let key = item.get_item_key();
+    let key = key(item);
     match key_items.iter().position(|i| i.0 == key) {
       Some(pos) => key_items[pos].1.push(idx),
       None => key_items.push((key, vec![idx])),
@@ -187,14 +402,14 @@ impl<T> From<Vec<T>> for DedupKeyList<T> {
   }
 }
 
-pub fn dedup_map<'a, I, F, R, RI>(items: &'a [I], f: F) -> Vec<RI>
+// Flattens the key lists returned by `f`, in item order.
+fn flatten_key_lists<'a, I, F, R, RI>(items: &'a [I], f: F) -> Vec<RI>
 where
   F: Fn(&'a I) -> R,
   R: Into<DedupKeyList<RI>>,
-  RI: PartialEq<RI> + Ord,
 {
   let kls: Vec<_> = items
-    .into_iter()
+    .iter()
     .map(|item| -> DedupKeyList<RI> { f(item).into() })
     .collect();
   let len = kls.iter().map(DedupKeyList::len).sum();
@@ -202,12 +417,85 @@ where
   for kl in kls {
     kl.move_to(&mut all_items);
   }
+  all_items
+}
+
+pub fn dedup_map<'a, I, F, R, RI>(items: &'a [I], f: F) -> Vec<RI>
+where
+  F: Fn(&'a I) -> R,
+  R: Into<DedupKeyList<RI>>,
+  RI: PartialEq<RI> + Ord,
+{
+  let mut all_items = flatten_key_lists(items, f);
   all_items.sort();
   all_items.dedup();
   all_items
 }
 
-impl<'a, T, K> HasItemKey<K> for (&'a T, K)
+/// Same as `dedup_map`, keeps the order each value is first seen.
+pub fn dedup_map_stable<'a, I, F, R, RI>(items: &'a [I], f: F) -> Vec<RI>
+where
+  F: Fn(&'a I) -> R,
+  R: Into<DedupKeyList<RI>>,
+  RI: Hash + Eq,
+{
+  dedup_map_stable_counts(items, f)
+    .into_iter()
+    .map(|(v, _)| v)
+    .collect()
+}
+
+/// Same as `dedup_map_stable`, with the number of times each value is seen.
+pub fn dedup_map_stable_counts<'a, I, F, R, RI>(items: &'a [I], f: F) -> Vec<(RI, usize)>
+where
+  F: Fn(&'a I) -> R,
+  R: Into<DedupKeyList<RI>>,
+  RI: Hash + Eq,
+{
+  let mut index = KeyIndex::new();
+  let mut counts: Vec<usize> = vec![];
+  for v in flatten_key_lists(items, f) {
+    let pos = index.insert(v);
+    if pos == counts.len() {
+      counts.push(0);
+    }
+    counts[pos] += 1;
+  }
+  index.into_keys().into_iter().zip(counts).collect()
+}
+
+/// Same as `dedup_map_stable`, for values that are only `PartialEq`.
+/// Quadratic in the number of distinct values.
+pub fn dedup_map_stable_by_eq<'a, I, F, R, RI>(items: &'a [I], f: F) -> Vec<RI>
+where
+  F: Fn(&'a I) -> R,
+  R: Into<DedupKeyList<RI>>,
+  RI: PartialEq,
+{
+  dedup_map_stable_counts_by_eq(items, f)
+    .into_iter()
+    .map(|(v, _)| v)
+    .collect()
+}
+
+/// Same as `dedup_map_stable_counts`, for values that are only `PartialEq`.
+pub fn dedup_map_stable_counts_by_eq<'a, I, F, R, RI>(items: &'a [I], f: F) -> Vec<(RI, usize)>
+where
+  F: Fn(&'a I) -> R,
+  R: Into<DedupKeyList<RI>>,
+  RI: PartialEq,
+{
+  let mut counts: Vec<(RI, usize)> = vec![];
+  for v in flatten_key_lists(items, f) {
+    match counts.iter_mut().find(|(seen, _)| *seen == v) {
+      Some((_, count)) => *count += 1,
+      None => counts.push((v, 1)),
+    }
+  }
+  counts
+}
+
+impl<T, K> HasItemKey<K> for (&T, K)
 where
   K: Clone,
 {
@@ -0,0 +1,260 @@
+//! Splits `ChangedItems` into batches for APIs that limit the number or the
+//! size of the items per call.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::reconcile::check_order;
+use super::{ChangeKind, ChangedItems};
+
+pub type WeightFn<'f, T> = Box<dyn Fn(&T) -> usize + 'f>;
+
+struct Weights<'f, TE, TN> {
+  limit: usize,
+  existing: WeightFn<'f, TE>,
+  new: WeightFn<'f, TN>,
+}
+
+/// Plans the batches of a changeset.
+///
+/// Batches of each kind follow `order`, inserts then updates then deletes by
+/// default. Items keep their order, a batch is a contiguous run of a bucket.
+pub struct BatchPlanner<'f, TE, TN> {
+  order: [ChangeKind; 3],
+  max_items: usize,
+  weights: Option<Weights<'f, TE, TN>>,
+}
+
+impl<'f, TE, TN> BatchPlanner<'f, TE, TN> {
+  pub fn new(max_items: usize) -> Self {
+    BatchPlanner {
+      order: [ChangeKind::Insert, ChangeKind::Update, ChangeKind::Delete],
+      max_items: 1,
+      weights: None,
+    }
+    .max_items(max_items)
+  }
+
+  pub fn max_items(mut self, max_items: usize) -> Self {
+    if max_items == 0 {
+      panic!("BatchPlanner max items should be greater than 0");
+    }
+    self.max_items = max_items;
+    self
+  }
+
+  pub fn order(mut self, order: [ChangeKind; 3]) -> Self {
+    check_order("BatchPlanner", &order);
+    self.order = order;
+    self
+  }
+
+  /// Limits the total weight of a batch, e.g. the request body size. Inserts
+  /// and updates are weighted by the new item, deletes by the existing item.
+  /// An item heavier than `limit` gets a batch of its own.
+  pub fn max_weight<FE, FN>(mut self, limit: usize, existing: FE, new: FN) -> Self
+  where
+    FE: Fn(&TE) -> usize + 'f,
+    FN: Fn(&TN) -> usize + 'f,
+  {
+    self.weights = Some(Weights {
+      limit,
+      existing: Box::new(existing),
+      new: Box::new(new),
+    });
+    self
+  }
+
+  pub fn plan<'a>(&self, changes: &ChangedItems<'a, TE, TN>) -> BatchPlan<'a, TE, TN> {
+    let new_weight = |ni: &TN| self.weights.as_ref().map_or(0, |w| (w.new)(ni));
+    let existing_weight = |ei: &TE| self.weights.as_ref().map_or(0, |w| (w.existing)(ei));
+    let mut batches = vec![];
+    for kind in &self.order {
+      match *kind {
+        ChangeKind::Insert => batches.extend(
+          self
+            .split(&changes.add, |ni| new_weight(ni))
+            .into_iter()
+            .map(Batch::Insert),
+        ),
+        ChangeKind::Update => batches.extend(
+          self
+            .split(&changes.update, |(_, ni)| new_weight(ni))
+            .into_iter()
+            .map(Batch::Update),
+        ),
+        ChangeKind::Delete => batches.extend(
+          self
+            .split(&changes.delete, |ei| existing_weight(ei))
+            .into_iter()
+            .map(Batch::Delete),
+        ),
+      }
+    }
+    BatchPlan { batches }
+  }
+
+  fn split<T, F>(&self, items: &[T], weight: F) -> Vec<Vec<T>>
+  where
+    T: Copy,
+    F: Fn(&T) -> usize,
+  {
+    let max_weight = self.weights.as_ref().map_or(usize::MAX, |w| w.limit);
+    let mut batches = vec![];
+    let mut batch: Vec<T> = vec![];
+    let mut batch_weight: usize = 0;
+    for item in items {
+      let item_weight = weight(item);
+      let full =
+        batch.len() == self.max_items || batch_weight.saturating_add(item_weight) > max_weight;
+      if !batch.is_empty() && full {
+        batches.push(batch);
+        batch = vec![];
+        batch_weight = 0;
+      }
+      batch.push(*item);
+      batch_weight = batch_weight.saturating_add(item_weight);
+    }
+    if !batch.is_empty() {
+      batches.push(batch);
+    }
+    batches
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Batch<'a, TE, TN>
+where
+  TE: 'a,
+  TN: 'a,
+{
+  Insert(Vec<&'a TN>),
+  Update(Vec<(&'a TE, &'a TN)>),
+  Delete(Vec<&'a TE>),
+}
+
+impl<'a, TE, TN> Batch<'a, TE, TN> {
+  pub fn kind(&self) -> ChangeKind {
+    match *self {
+      Batch::Insert(_) => ChangeKind::Insert,
+      Batch::Update(_) => ChangeKind::Update,
+      Batch::Delete(_) => ChangeKind::Delete,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    match *self {
+      Batch::Insert(ref items) => items.len(),
+      Batch::Update(ref items) => items.len(),
+      Batch::Delete(ref items) => items.len(),
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+/// Position in a `BatchPlan`, to resume a run that failed or was
+/// interrupted. Only valid for a plan of the same changeset and planner.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Checkpoint {
+  /// Index of the next batch to run.
+  pub next_batch: usize,
+}
+
+/// A batch that still failed after all attempts.
+#[derive(Debug, PartialEq)]
+pub struct BatchFailure<E> {
+  pub batch: usize,
+  pub kind: ChangeKind,
+  /// Errors of each attempt.
+  pub errors: Vec<E>,
+}
+
+/// Result of `BatchPlan::run`.
+#[derive(Debug, PartialEq)]
+pub struct BatchRunReport<E> {
+  /// Where the run stopped, pass it to `run` to resume.
+  pub checkpoint: Checkpoint,
+  pub failure: Option<BatchFailure<E>>,
+}
+
+impl<E> BatchRunReport<E> {
+  pub fn is_ok(&self) -> bool {
+    self.failure.is_none()
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BatchPlan<'a, TE, TN>
+where
+  TE: 'a,
+  TN: 'a,
+{
+  pub batches: Vec<Batch<'a, TE, TN>>,
+}
+
+impl<'a, TE, TN> BatchPlan<'a, TE, TN> {
+  pub fn len(&self) -> usize {
+    self.batches.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.batches.is_empty()
+  }
+
+  pub fn is_done(&self, checkpoint: Checkpoint) -> bool {
+    checkpoint.next_batch >= self.batches.len()
+  }
+
+  /// Batches from the checkpoint on, with their index.
+  pub fn iter_from(
+    &self,
+    checkpoint: Checkpoint,
+  ) -> impl Iterator<Item = (usize, &Batch<'a, TE, TN>)> {
+    self.batches.iter().enumerate().skip(checkpoint.next_batch)
+  }
+
+  /// Runs the batches from the checkpoint on, trying each batch up to
+  /// `max_attempts` times. Stops at the first batch that fails every attempt.
+  pub fn run<E, F>(
+    &self,
+    checkpoint: Checkpoint,
+    max_attempts: usize,
+    mut f: F,
+  ) -> BatchRunReport<E>
+  where
+    F: FnMut(&Batch<'a, TE, TN>) -> Result<(), E>,
+  {
+    if max_attempts == 0 {
+      panic!("BatchPlan max attempts should be greater than 0");
+    }
+    for (index, batch) in self.iter_from(checkpoint) {
+      let mut errors = vec![];
+      while errors.len() < max_attempts {
+        match f(batch) {
+          Ok(()) => break,
+          Err(error) => errors.push(error),
+        }
+      }
+      if errors.len() == max_attempts {
+        return BatchRunReport {
+          checkpoint: Checkpoint { next_batch: index },
+          failure: Some(BatchFailure {
+            batch: index,
+            kind: batch.kind(),
+            errors,
+          }),
+        };
+      }
+    }
+    BatchRunReport {
+      checkpoint: Checkpoint {
+        next_batch: self.batches.len().max(checkpoint.next_batch),
+      },
+      failure: None,
+    }
+  }
+}
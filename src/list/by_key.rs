@@ -0,0 +1,186 @@
+//! Diffs of arbitrary iterables and of maps keyed by the item key, and the
+//! `DiffByKey` extension trait.
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use super::{dup_items_by, hashed_changed_items, ChangedItems, HasItemKey};
+
+/// Same as `get_changed_items`, for any iterable of references, e.g. a
+/// `VecDeque` or the values of a map. The keys are indexed in a `HashMap`
+/// like `get_changed_items_hashed`, so they must be `Hash + Eq` where
+/// `get_changed_items` only needs `PartialEq`.
+pub fn get_changed_items_iter<'a, K, TE, TN, IE, IN>(
+  existing_items: IE,
+  new_items: IN,
+) -> ChangedItems<'a, TE, TN>
+where
+  K: Hash + Eq,
+  TE: 'a,
+  &'a TE: HasItemKey<K>,
+  TN: 'a,
+  &'a TN: HasItemKey<K>,
+  IE: IntoIterator<Item = &'a TE>,
+  IN: IntoIterator<Item = &'a TN>,
+{
+  hashed_changed_items(
+    existing_items,
+    new_items,
+    |ei| ei.get_item_key(),
+    |ni| ni.get_item_key(),
+  )
+}
+
+/// Same as `get_dup_items`, for any iterable of references. Indexes are
+/// positions in the iteration.
+pub fn get_dup_items_iter<'a, K, T, I>(items: I) -> Vec<(K, Vec<(usize, &'a T)>)>
+where
+  K: PartialEq,
+  T: 'a,
+  &'a T: HasItemKey<K>,
+  I: IntoIterator<Item = &'a T>,
+{
+  let items: Vec<&T> = items.into_iter().collect();
+  dup_items_by(&items, |i| i.get_item_key())
+    .into_iter()
+    .map(|(key, dups)| (key, dups.into_iter().map(|(idx, i)| (idx, *i)).collect()))
+    .collect()
+}
+
+// Diffs 2 maps keyed by the item key, updates and adds follow `new_items`.
+fn map_changed_items<'a, K, TE, TN, IE, IN, GE, CN>(
+  existing_items: IE,
+  new_items: IN,
+  existing_get: GE,
+  new_contains: CN,
+) -> ChangedItems<'a, TE, TN>
+where
+  K: 'a,
+  TE: 'a,
+  TN: 'a,
+  IE: IntoIterator<Item = (&'a K, &'a TE)>,
+  IN: IntoIterator<Item = (&'a K, &'a TN)>,
+  GE: Fn(&K) -> Option<&'a TE>,
+  CN: Fn(&K) -> bool,
+{
+  let mut result = ChangedItems {
+    add: vec![],
+    update: vec![],
+    delete: vec![],
+  };
+  for (key, ni) in new_items {
+    match existing_get(key) {
+      Some(ei) => result.update.push((ei, ni)),
+      None => result.add.push(ni),
+    }
+  }
+  result.delete = existing_items
+    .into_iter()
+    .filter(|(key, _)| !new_contains(key))
+    .map(|(_, ei)| ei)
+    .collect();
+  result
+}
+
+/// Diffs 2 `HashMap`s whose keys are the item keys. The order of the result
+/// follows the map iteration order.
+pub fn get_changed_hash_map_items<'a, K, TE, TN>(
+  existing_items: &'a HashMap<K, TE>,
+  new_items: &'a HashMap<K, TN>,
+) -> ChangedItems<'a, TE, TN>
+where
+  K: Hash + Eq,
+{
+  map_changed_items(
+    existing_items,
+    new_items,
+    |key| existing_items.get(key),
+    |key| new_items.contains_key(key),
+  )
+}
+
+/// Diffs 2 `BTreeMap`s whose keys are the item keys. The result is sorted by
+/// key.
+pub fn get_changed_btree_map_items<'a, K, TE, TN>(
+  existing_items: &'a BTreeMap<K, TE>,
+  new_items: &'a BTreeMap<K, TN>,
+) -> ChangedItems<'a, TE, TN>
+where
+  K: Ord,
+{
+  map_changed_items(
+    existing_items,
+    new_items,
+    |key| existing_items.get(key),
+    |key| new_items.contains_key(key),
+  )
+}
+
+/// `existing.diff_by_key(&new)`, for slices, `Vec`s and maps keyed by the
+/// item key.
+pub trait DiffByKey<'a, K, Rhs: ?Sized> {
+  type Existing: 'a;
+  type New: 'a;
+
+  fn diff_by_key(&'a self, new_items: &'a Rhs) -> ChangedItems<'a, Self::Existing, Self::New>;
+}
+
+impl<'a, K, TE, TN> DiffByKey<'a, K, [TN]> for [TE]
+where
+  K: Hash + Eq,
+  TE: 'a,
+  &'a TE: HasItemKey<K>,
+  TN: 'a,
+  &'a TN: HasItemKey<K>,
+{
+  type Existing = TE;
+  type New = TN;
+
+  fn diff_by_key(&'a self, new_items: &'a [TN]) -> ChangedItems<'a, TE, TN> {
+    get_changed_items_iter(self, new_items)
+  }
+}
+
+impl<'a, K, TE, TN> DiffByKey<'a, K, Vec<TN>> for [TE]
+where
+  K: Hash + Eq,
+  TE: 'a,
+  &'a TE: HasItemKey<K>,
+  TN: 'a,
+  &'a TN: HasItemKey<K>,
+{
+  type Existing = TE;
+  type New = TN;
+
+  fn diff_by_key(&'a self, new_items: &'a Vec<TN>) -> ChangedItems<'a, TE, TN> {
+    get_changed_items_iter(self, new_items)
+  }
+}
+
+impl<'a, K, TE, TN> DiffByKey<'a, K, HashMap<K, TN>> for HashMap<K, TE>
+where
+  K: Hash + Eq + 'a,
+  TE: 'a,
+  TN: 'a,
+{
+  type Existing = TE;
+  type New = TN;
+
+  fn diff_by_key(&'a self, new_items: &'a HashMap<K, TN>) -> ChangedItems<'a, TE, TN> {
+    get_changed_hash_map_items(self, new_items)
+  }
+}
+
+impl<'a, K, TE, TN> DiffByKey<'a, K, BTreeMap<K, TN>> for BTreeMap<K, TE>
+where
+  K: Ord + 'a,
+  TE: 'a,
+  TN: 'a,
+{
+  type Existing = TE;
+  type New = TN;
+
+  fn diff_by_key(&'a self, new_items: &'a BTreeMap<K, TN>) -> ChangedItems<'a, TE, TN> {
+    get_changed_btree_map_items(self, new_items)
+  }
+}
@@ -0,0 +1,343 @@
+//! Owned version of `ChangedItems`.
+//!
+//! With the `serde` feature, a `ChangeSet` serializes to:
+//!
+//! ```json
+//! {
+//!   "add": [{ "key": 3, "item": { ... } }],
+//!   "update": [{ "key": 1, "existing": { ... }, "new": { ... } }],
+//!   "delete": [{ "key": 2, "item": { ... } }]
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{ChangeKind, ChangedItems, HasItemKey};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KeyedItem<K, T> {
+  pub key: K,
+  pub item: T,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KeyedUpdate<K, TE, TN> {
+  pub key: K,
+  pub existing: TE,
+  pub new: TN,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChangeSet<K, TE, TN> {
+  pub add: Vec<KeyedItem<K, TN>>,
+  pub update: Vec<KeyedUpdate<K, TE, TN>>,
+  pub delete: Vec<KeyedItem<K, TE>>,
+}
+
+impl<K, TE, TN> ChangeSet<K, TE, TN> {
+  pub fn is_empty(&self) -> bool {
+    self.add.is_empty() && self.update.is_empty() && self.delete.is_empty()
+  }
+
+  /// Borrows the changeset as `ChangedItems`, e.g. to replay it with a
+  /// `Reconciler`.
+  pub fn as_changed_items(&self) -> ChangedItems<'_, TE, TN> {
+    ChangedItems {
+      add: self.add.iter().map(|i| &i.item).collect(),
+      update: self.update.iter().map(|u| (&u.existing, &u.new)).collect(),
+      delete: self.delete.iter().map(|i| &i.item).collect(),
+    }
+  }
+}
+
+/// An existing item deleted several times, as `get_changed_items` does with
+/// duplicated keys, is deleted once.
+impl<'a, K, TE, TN> From<ChangedItems<'a, TE, TN>> for ChangeSet<K, TE, TN>
+where
+  TE: Clone,
+  TN: Clone,
+  &'a TE: HasItemKey<K>,
+  &'a TN: HasItemKey<K>,
+{
+  fn from(changes: ChangedItems<'a, TE, TN>) -> Self {
+    let mut deleted = HashSet::new();
+    ChangeSet {
+      add: changes
+        .add
+        .into_iter()
+        .map(|ni| KeyedItem {
+          key: ni.get_item_key(),
+          item: ni.clone(),
+        })
+        .collect(),
+      update: changes
+        .update
+        .into_iter()
+        .map(|(ei, ni)| KeyedUpdate {
+          key: ni.get_item_key(),
+          existing: ei.clone(),
+          new: ni.clone(),
+        })
+        .collect(),
+      delete: changes
+        .delete
+        .into_iter()
+        .filter(|&ei| deleted.insert(ei as *const TE))
+        .map(|ei| KeyedItem {
+          key: ei.get_item_key(),
+          item: ei.clone(),
+        })
+        .collect(),
+    }
+  }
+}
+
+// A change of one item
+enum Change<T> {
+  Insert(T),
+  Update(T, T),
+  Delete(T),
+}
+
+impl<T> Change<T> {
+  fn kind(&self) -> ChangeKind {
+    match *self {
+      Change::Insert(_) => ChangeKind::Insert,
+      Change::Update(..) => ChangeKind::Update,
+      Change::Delete(_) => ChangeKind::Delete,
+    }
+  }
+}
+
+impl<K, T> ChangeSet<K, T, T> {
+  fn into_changes(self) -> Vec<(K, Change<T>)> {
+    let mut changes: Vec<_> = self
+      .add
+      .into_iter()
+      .map(|i| (i.key, Change::Insert(i.item)))
+      .collect();
+    changes.extend(
+      self
+        .update
+        .into_iter()
+        .map(|u| (u.key, Change::Update(u.existing, u.new))),
+    );
+    changes.extend(
+      self
+        .delete
+        .into_iter()
+        .map(|i| (i.key, Change::Delete(i.item))),
+    );
+    changes
+  }
+
+  fn from_changes<I>(changes: I) -> Self
+  where
+    I: IntoIterator<Item = (K, Change<T>)>,
+  {
+    let mut set = ChangeSet {
+      add: vec![],
+      update: vec![],
+      delete: vec![],
+    };
+    for (key, change) in changes {
+      match change {
+        Change::Insert(item) => set.add.push(KeyedItem { key, item }),
+        Change::Update(existing, new) => set.update.push(KeyedUpdate { key, existing, new }),
+        Change::Delete(item) => set.delete.push(KeyedItem { key, item }),
+      }
+    }
+    set
+  }
+
+  /// Combines this changeset with the `next` one, applying the result is the
+  /// same as applying this changeset and then `next`.
+  ///
+  /// Changes are matched by key and item, so keys with several items are
+  /// supported: an update or a delete of `next` follows the change of this
+  /// changeset that produced an equal item. An insert followed by a delete of
+  /// the same item cancels out, a delete followed by an insert of the same
+  /// key becomes an update. Fails if `next` changes an item this changeset
+  /// deleted.
+  pub fn compose(self, next: Self) -> Result<Self, ComposeError<K>>
+  where
+    K: Hash + Eq + Clone,
+    T: PartialEq,
+  {
+    // positions of the changes of each key
+    let mut index: HashMap<K, Vec<usize>> = HashMap::new();
+    // each change, and whether `next` already changed its item
+    let mut changes: Vec<(K, Option<Change<T>>, bool)> = vec![];
+    for (key, change) in self.into_changes() {
+      index.entry(key.clone()).or_default().push(changes.len());
+      changes.push((key, Some(change), false));
+    }
+
+    for (key, second) in next.into_changes() {
+      let first = {
+        let positions = index.get(&key).map_or(&[][..], |positions| &positions[..]);
+        let find = |f: &dyn Fn(&Change<T>) -> bool| {
+          positions.iter().cloned().find(|&pos| match changes[pos] {
+            (_, Some(ref change), false) => f(change),
+            _ => false,
+          })
+        };
+        match second {
+          Change::Insert(_) => find(&|first| matches!(*first, Change::Delete(_))),
+          Change::Update(ref item, _) | Change::Delete(ref item) => {
+            let produced = find(&|first| match *first {
+              Change::Insert(ref new) | Change::Update(_, ref new) => new == item,
+              Change::Delete(_) => false,
+            });
+            let deleted = find(&|first| match *first {
+              Change::Delete(ref existing) => existing == item,
+              _ => false,
+            });
+            if produced.is_none() && deleted.is_some() {
+              return Err(ComposeError {
+                key,
+                first: ChangeKind::Delete,
+                second: second.kind(),
+              });
+            }
+            produced
+          }
+        }
+      };
+
+      let pos = match first {
+        Some(pos) => pos,
+        // an item this changeset didn't change
+        None => {
+          index.entry(key.clone()).or_default().push(changes.len());
+          changes.push((key, Some(second), true));
+          continue;
+        }
+      };
+      let composed = match (changes[pos].1.take(), second) {
+        (Some(Change::Insert(_)), Change::Update(_, new)) => Some(Change::Insert(new)),
+        (Some(Change::Insert(_)), Change::Delete(_)) => None,
+        (Some(Change::Update(existing, _)), Change::Update(_, new)) => {
+          Some(Change::Update(existing, new))
+        }
+        (Some(Change::Update(existing, _)), Change::Delete(_)) => Some(Change::Delete(existing)),
+        (Some(Change::Delete(existing)), Change::Insert(new)) => {
+          Some(Change::Update(existing, new))
+        }
+        _ => unreachable!(),
+      };
+      changes[pos].1 = composed;
+      changes[pos].2 = true;
+    }
+
+    Ok(Self::from_changes(changes.into_iter().filter_map(
+      |(key, change, _)| change.map(|change| (key, change)),
+    )))
+  }
+
+  /// Returns the changeset that reverts this one.
+  pub fn invert(self) -> Self {
+    Self::from_changes(
+      self
+        .into_changes()
+        .into_iter()
+        .map(|(key, change)| match change {
+          Change::Insert(item) => (key, Change::Delete(item)),
+          Change::Update(existing, new) => (key, Change::Update(new, existing)),
+          Change::Delete(item) => (key, Change::Insert(item)),
+        }),
+    )
+  }
+
+  /// Removes the updates that don't change the item.
+  pub fn compact(mut self) -> Self
+  where
+    T: PartialEq,
+  {
+    self.update.retain(|u| u.existing != u.new);
+    self
+  }
+
+  /// Applies the changeset to `items`: deleted items are removed, updated
+  /// items are replaced in place and added items are appended.
+  ///
+  /// Each delete and update applies to one item with its key, preferably one
+  /// equal to the changed item, so keys with several items are supported.
+  pub fn apply(&self, items: &mut Vec<T>)
+  where
+    K: Hash + Eq,
+    T: HasItemKey<K> + Clone + PartialEq,
+  {
+    let mut index: HashMap<K, Vec<usize>> = HashMap::new();
+    for (pos, item) in items.iter().enumerate() {
+      index.entry(item.get_item_key()).or_default().push(pos);
+    }
+
+    // position of the item each delete and update applies to, equal items
+    // first, then any item with the key
+    let mut delete = vec![None; self.delete.len()];
+    let mut update = vec![None; self.update.len()];
+    for &equal in &[true, false] {
+      let mut find = |key: &K, item: &T| {
+        let positions = index.get_mut(key)?;
+        let at = positions
+          .iter()
+          .position(|&pos| !equal || items[pos] == *item)?;
+        Some(positions.remove(at))
+      };
+      for (pos, i) in delete.iter_mut().zip(&self.delete) {
+        if pos.is_none() {
+          *pos = find(&i.key, &i.item);
+        }
+      }
+      for (pos, u) in update.iter_mut().zip(&self.update) {
+        if pos.is_none() {
+          *pos = find(&u.key, &u.existing);
+        }
+      }
+    }
+
+    for (pos, u) in update.into_iter().zip(&self.update) {
+      if let Some(pos) = pos {
+        items[pos] = u.new.clone();
+      }
+    }
+    let delete: HashSet<usize> = delete.into_iter().flatten().collect();
+    let mut pos = 0;
+    items.retain(|_| {
+      pos += 1;
+      !delete.contains(&(pos - 1))
+    });
+    items.extend(self.add.iter().map(|i| i.item.clone()));
+  }
+}
+
+/// Changes of `ChangeSet::compose` that can't follow each other.
+#[derive(Debug, PartialEq)]
+pub struct ComposeError<K> {
+  pub key: K,
+  /// The change of the first changeset.
+  pub first: ChangeKind,
+  pub second: ChangeKind,
+}
+
+impl<K: fmt::Debug> fmt::Display for ComposeError<K> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "{:?} of key {:?} can't follow {:?}",
+      self.second, self.key, self.first
+    )
+  }
+}
+
+impl<K: fmt::Debug> Error for ComposeError<K> {}
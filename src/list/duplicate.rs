@@ -0,0 +1,210 @@
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+use std::ops::Deref;
+
+use super::{HasItemKey, ListSide};
+use crate::key_index::{group_keyed, KeyIndex};
+
+pub type MergeFn<'f, T> = Box<dyn Fn(&T, &T) -> T + 'f>;
+
+/// How `get_changed_items_with_policy` handles items sharing the same key.
+pub enum DuplicatePolicy<'f, T> {
+  /// Keep the first item. This is what `get_changed_items` does with
+  /// existing items.
+  KeepFirst,
+  /// Keep the last item. This is what `get_changed_items` does with new
+  /// items matching an existing key. Unlike `get_changed_items`, which adds
+  /// every new item of a key without a match, only the last one is added.
+  KeepLast,
+  /// Fail with a `DuplicateKeyError`.
+  Error,
+  /// Combine the items in list order with the closure.
+  Merge(MergeFn<'f, T>),
+}
+
+impl<'f, T> DuplicatePolicy<'f, T> {
+  pub fn merge<F>(f: F) -> Self
+  where
+    F: Fn(&T, &T) -> T + 'f,
+  {
+    DuplicatePolicy::Merge(Box::new(f))
+  }
+
+  // Returns the item to keep, and the position of the item it replaces.
+  fn resolve<'a>(&self, items: &[&'a T]) -> (Resolved<'a, T>, usize) {
+    match *self {
+      DuplicatePolicy::KeepLast => {
+        let pos = items.len() - 1;
+        (Resolved::Borrowed(items[pos]), pos)
+      }
+      DuplicatePolicy::Merge(ref f) if items.len() > 1 => {
+        let merged = items[2..]
+          .iter()
+          .fold(f(items[0], items[1]), |merged, item| f(&merged, item));
+        (Resolved::Merged(merged), 0)
+      }
+      _ => (Resolved::Borrowed(items[0]), 0),
+    }
+  }
+
+  fn check<'a, K>(&self, items: &'a [T], side: ListSide) -> Result<(), DuplicateKeyError<K>>
+  where
+    K: Hash + Eq,
+    T: 'a,
+    &'a T: HasItemKey<K>,
+  {
+    if let DuplicatePolicy::Error = *self {
+      let keys: Vec<_> = group_keyed(
+        items
+          .iter()
+          .enumerate()
+          .map(|(idx, item)| (idx, item.get_item_key())),
+      )
+      .into_iter()
+      .filter(|(_, idxs)| idxs.len() > 1)
+      .collect();
+      if !keys.is_empty() {
+        return Err(DuplicateKeyError { side, keys });
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Items sharing the same key with `DuplicatePolicy::Error`.
+#[derive(Debug, PartialEq)]
+pub struct DuplicateKeyError<K> {
+  pub side: ListSide,
+  /// Duplicated keys and the indexes of the items.
+  pub keys: Vec<(K, Vec<usize>)>,
+}
+
+impl<K: fmt::Debug> fmt::Display for DuplicateKeyError<K> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "duplicated keys in {:?} items:", self.side)?;
+    for (key, idxs) in &self.keys {
+      write!(f, " {:?} at {:?}", key, idxs)?;
+    }
+    Ok(())
+  }
+}
+
+impl<K: fmt::Debug> Error for DuplicateKeyError<K> {}
+
+/// An item from the input list, or the result of `DuplicatePolicy::Merge`.
+#[derive(Debug, PartialEq)]
+pub enum Resolved<'a, T: 'a> {
+  Borrowed(&'a T),
+  Merged(T),
+}
+
+impl<'a, T> Deref for Resolved<'a, T> {
+  type Target = T;
+
+  fn deref(&self) -> &T {
+    match *self {
+      Resolved::Borrowed(item) => item,
+      Resolved::Merged(ref item) => item,
+    }
+  }
+}
+
+pub struct ResolvedChangedItems<'a, TE, TN>
+where
+  TE: 'a,
+  TN: 'a,
+{
+  pub add: Vec<Resolved<'a, TN>>,
+  pub update: Vec<(Resolved<'a, TE>, Resolved<'a, TN>)>,
+  pub delete: Vec<&'a TE>,
+}
+
+/// Same as `get_changed_items`, with configurable handling of duplicated keys.
+///
+/// Of the existing items sharing a key, the one kept by `existing_policy` is
+/// updated and the rest are deleted. A merged existing item replaces the
+/// first one. Of the new items sharing a key, only the one kept by
+/// `new_policy` is added or updated.
+///
+/// With `KeepFirst` for the existing items and `KeepLast` for the new ones,
+/// the result is the one of `get_changed_items`, except for new items
+/// sharing a key: `get_changed_items` adds all of them if no existing item
+/// has the key, and deletes the extra existing items again for each of
+/// them. This adds the last one and deletes each extra item once.
+pub fn get_changed_items_with_policy<'a, K, TE, TN>(
+  existing_items: &'a [TE],
+  new_items: &'a [TN],
+  existing_policy: &DuplicatePolicy<TE>,
+  new_policy: &DuplicatePolicy<TN>,
+) -> Result<ResolvedChangedItems<'a, TE, TN>, DuplicateKeyError<K>>
+where
+  K: Hash + Eq,
+  TE: 'a,
+  &'a TE: HasItemKey<K>,
+  TN: 'a,
+  &'a TN: HasItemKey<K>,
+{
+  existing_policy.check(existing_items, ListSide::Existing)?;
+  new_policy.check(new_items, ListSide::New)?;
+
+  let mut existing_index = KeyIndex::new();
+  let mut existing_groups: Vec<Vec<&TE>> = vec![];
+  let mut existing = vec![];
+  for ei in existing_items {
+    let group = existing_index.insert(ei.get_item_key());
+    if group == existing_groups.len() {
+      existing_groups.push(vec![]);
+    }
+    existing_groups[group].push(ei);
+    existing.push((ei, group));
+  }
+
+  // new items sharing the same key, and the matching existing group
+  let mut new_index = KeyIndex::new();
+  let mut new_groups = vec![];
+  for ni in new_items {
+    let key = ni.get_item_key();
+    let existing_group = existing_index.get(&key);
+    let group = new_index.insert(key);
+    if group == new_groups.len() {
+      new_groups.push((vec![], existing_group));
+    }
+    new_groups[group].0.push(ni);
+  }
+
+  let mut result = ResolvedChangedItems {
+    add: vec![],
+    update: vec![],
+    delete: vec![],
+  };
+
+  let mut matched = vec![false; existing_groups.len()];
+  for (items, existing_group) in new_groups {
+    let (ni, _) = new_policy.resolve(&items);
+    match existing_group {
+      Some(group) => {
+        let items = &existing_groups[group];
+        let (ei, pos) = existing_policy.resolve(items);
+        result.delete.extend(
+          items
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != pos)
+            .map(|(_, ei)| *ei),
+        );
+        result.update.push((ei, ni));
+        matched[group] = true;
+      }
+      None => result.add.push(ni),
+    }
+  }
+
+  for (ei, group) in existing {
+    if !matched[group] {
+      result.delete.push(ei);
+    }
+  }
+
+  Ok(result)
+}
@@ -0,0 +1,141 @@
+use std::hash::Hash;
+use std::vec::IntoIter;
+
+use super::HasItemKey;
+use crate::key_index::KeyIndex;
+
+// Indexes `right` by key. Returns the position of each key, the items of each
+// key in first-seen order, and the key position of each item of `right`.
+fn index_right<'a, K, R>(right: &'a [R]) -> (KeyIndex<K>, Vec<Vec<&'a R>>, Vec<usize>)
+where
+  K: Hash + Eq,
+  &'a R: HasItemKey<K>,
+{
+  let mut index = KeyIndex::new();
+  let mut groups: Vec<Vec<&R>> = vec![];
+  let mut positions = vec![];
+  for item in right {
+    let group = index.insert(item.get_item_key());
+    if group == groups.len() {
+      groups.push(vec![]);
+    }
+    groups[group].push(item);
+    positions.push(group);
+  }
+  (index, groups, positions)
+}
+
+/// Pairs of items with the same key, in `left` order then `right` order.
+pub fn inner_join<'a, K, L, R>(left: &'a [L], right: &'a [R]) -> IntoIter<(&'a L, &'a R)>
+where
+  K: Hash + Eq,
+  &'a L: HasItemKey<K>,
+  &'a R: HasItemKey<K>,
+{
+  let (index, groups, _) = index_right(right);
+  let mut pairs = vec![];
+  for l in left {
+    if let Some(group) = index.get(&l.get_item_key()) {
+      pairs.extend(groups[group].iter().map(|r| (l, *r)));
+    }
+  }
+  pairs.into_iter()
+}
+
+/// Same as `inner_join`, items in `left` without a match are paired with `None`.
+pub fn left_join<'a, K, L, R>(left: &'a [L], right: &'a [R]) -> IntoIter<(&'a L, Option<&'a R>)>
+where
+  K: Hash + Eq,
+  &'a L: HasItemKey<K>,
+  &'a R: HasItemKey<K>,
+{
+  let (index, groups, _) = index_right(right);
+  let mut pairs = vec![];
+  for l in left {
+    match index.get(&l.get_item_key()) {
+      Some(group) => pairs.extend(groups[group].iter().map(|r| (l, Some(*r)))),
+      None => pairs.push((l, None)),
+    }
+  }
+  pairs.into_iter()
+}
+
+/// Same as `left_join` with the sides swapped, pairs follow `right` order.
+pub fn right_join<'a, K, L, R>(left: &'a [L], right: &'a [R]) -> IntoIter<(Option<&'a L>, &'a R)>
+where
+  K: Hash + Eq,
+  &'a L: HasItemKey<K>,
+  &'a R: HasItemKey<K>,
+{
+  left_join(right, left)
+    .map(|(r, l)| (l, r))
+    .collect::<Vec<_>>()
+    .into_iter()
+}
+
+/// `left_join`, followed by the items in `right` without a match, in `right`
+/// order.
+pub fn full_join<'a, K, L, R>(
+  left: &'a [L],
+  right: &'a [R],
+) -> IntoIter<(Option<&'a L>, Option<&'a R>)>
+where
+  K: Hash + Eq,
+  &'a L: HasItemKey<K>,
+  &'a R: HasItemKey<K>,
+{
+  let (index, groups, positions) = index_right(right);
+  let mut matched = vec![false; groups.len()];
+  let mut pairs = vec![];
+  for l in left {
+    match index.get(&l.get_item_key()) {
+      Some(group) => {
+        matched[group] = true;
+        pairs.extend(groups[group].iter().map(|r| (Some(l), Some(*r))));
+      }
+      None => pairs.push((Some(l), None)),
+    }
+  }
+  for (r, group) in right.iter().zip(positions) {
+    if !matched[group] {
+      pairs.push((None, Some(r)));
+    }
+  }
+  pairs.into_iter()
+}
+
+/// Groups both lists by key, in first-seen order with `left` first.
+pub fn group_join<'a, K, L, R>(
+  left: &'a [L],
+  right: &'a [R],
+) -> IntoIter<(K, Vec<&'a L>, Vec<&'a R>)>
+where
+  K: Hash + Eq,
+  &'a L: HasItemKey<K>,
+  &'a R: HasItemKey<K>,
+{
+  let mut index = KeyIndex::new();
+  let mut groups: Vec<(Vec<&L>, Vec<&R>)> = vec![];
+  for l in left {
+    let group = index.insert(l.get_item_key());
+    if group == groups.len() {
+      groups.push((vec![], vec![]));
+    }
+    groups[group].0.push(l);
+  }
+  for r in right {
+    let group = index.insert(r.get_item_key());
+    if group == groups.len() {
+      groups.push((vec![], vec![]));
+    }
+    groups[group].1.push(r);
+  }
+
+  index
+    .into_keys()
+    .into_iter()
+    .zip(groups)
+    .map(|(key, (l, r))| (key, l, r))
+    .collect::<Vec<_>>()
+    .into_iter()
+}
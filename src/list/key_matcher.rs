@@ -0,0 +1,203 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use super::ChangedItems;
+use crate::key_index::KeyIndex;
+
+// Numbers of the keys of each item, equal keys get the same number.
+type KeyIds = Vec<Option<usize>>;
+
+// A key of the chain, with its key type erased so each key can have its own.
+trait MatchKey<TE, TN> {
+  fn name(&self) -> &'static str;
+
+  fn key_ids(&self, existing_items: &[TE], new_items: &[TN]) -> (KeyIds, KeyIds);
+}
+
+struct KeyFns<K, FE, FN> {
+  name: &'static str,
+  existing: FE,
+  new: FN,
+  key: PhantomData<fn() -> K>,
+}
+
+impl<K, TE, TN, FE, FN> MatchKey<TE, TN> for KeyFns<K, FE, FN>
+where
+  K: Hash + Eq,
+  FE: Fn(&TE) -> Option<K>,
+  FN: Fn(&TN) -> Option<K>,
+{
+  fn name(&self) -> &'static str {
+    self.name
+  }
+
+  fn key_ids(&self, existing_items: &[TE], new_items: &[TN]) -> (KeyIds, KeyIds) {
+    let mut index = KeyIndex::new();
+    let existing = existing_items
+      .iter()
+      .map(|ei| (self.existing)(ei).map(|k| index.insert(k)))
+      .collect();
+    let new = new_items
+      .iter()
+      .map(|ni| (self.new)(ni).map(|k| index.insert(k)))
+      .collect();
+    (existing, new)
+  }
+}
+
+/// Matches existing and new items by a chain of keys, e.g. by id if both
+/// sides have one, otherwise by SKU, otherwise by barcode.
+///
+/// Keys are tried in the order they are added. Each key matches the items
+/// left unmatched by the previous keys, a new item is paired with the first
+/// unmatched existing item with the same key.
+///
+/// A key that both items have but that differs blocks the lower priority
+/// keys, e.g. a listing with id 4 never matches a listing with id 5 by SKU.
+/// `allow_key_conflicts` turns this off.
+pub struct KeyMatcher<TE, TN> {
+  keys: Vec<Box<dyn MatchKey<TE, TN>>>,
+  allow_key_conflicts: bool,
+}
+
+impl<TE, TN> Default for KeyMatcher<TE, TN> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<TE, TN> KeyMatcher<TE, TN> {
+  pub fn new() -> Self {
+    KeyMatcher {
+      keys: vec![],
+      allow_key_conflicts: false,
+    }
+  }
+
+  /// Adds a key with a lower priority than the keys already added. The
+  /// closures return `None` if an item doesn't have the key. Each key has its
+  /// own type, e.g. an `i64` id and a `String` SKU.
+  pub fn key<K, FE, FN>(mut self, name: &'static str, existing: FE, new: FN) -> Self
+  where
+    K: Hash + Eq + 'static,
+    FE: Fn(&TE) -> Option<K> + 'static,
+    FN: Fn(&TN) -> Option<K> + 'static,
+  {
+    self.keys.push(Box::new(KeyFns {
+      name,
+      existing,
+      new,
+      key: PhantomData,
+    }));
+    self
+  }
+
+  /// Lets a lower priority key match items whose higher priority keys
+  /// differ.
+  pub fn allow_key_conflicts(mut self) -> Self {
+    self.allow_key_conflicts = true;
+    self
+  }
+
+  /// Updates follow the order of `new_items`, deletes the order of
+  /// `existing_items`.
+  pub fn get_changed_items<'a>(
+    &self,
+    existing_items: &'a [TE],
+    new_items: &'a [TN],
+  ) -> MatchedChangedItems<'a, TE, TN> {
+    // key ids of each item, one list per key
+    let (existing_keys, new_keys): (Vec<KeyIds>, Vec<KeyIds>) = self
+      .keys
+      .iter()
+      .map(|key| key.key_ids(existing_items, new_items))
+      .unzip();
+    // both items have one of the first `level` keys, with different values
+    let conflicts = |level: usize, ei: usize, ni: usize| {
+      !self.allow_key_conflicts
+        && existing_keys[..level]
+          .iter()
+          .zip(&new_keys[..level])
+          .any(|(e, n)| match (e[ei], n[ni]) {
+            (Some(e), Some(n)) => e != n,
+            _ => false,
+          })
+    };
+
+    let mut existing_matched = vec![false; existing_items.len()];
+    let mut new_matches: Vec<Option<(usize, &'static str)>> = vec![None; new_items.len()];
+
+    for (level, key) in self.keys.iter().enumerate() {
+      let mut index: HashMap<usize, VecDeque<usize>> = HashMap::new();
+      for (ei, k) in existing_keys[level].iter().enumerate() {
+        if existing_matched[ei] {
+          continue;
+        }
+        if let Some(k) = *k {
+          index.entry(k).or_default().push_back(ei);
+        }
+      }
+      for (ni, m) in new_matches.iter_mut().enumerate() {
+        if m.is_some() {
+          continue;
+        }
+        let candidates = match new_keys[level][ni].and_then(|k| index.get_mut(&k)) {
+          Some(candidates) => candidates,
+          None => continue,
+        };
+        if let Some(pos) = candidates.iter().position(|&ei| !conflicts(level, ei, ni)) {
+          let ei = candidates.remove(pos).unwrap();
+          existing_matched[ei] = true;
+          *m = Some((ei, key.name()));
+        }
+      }
+    }
+
+    let mut changes = MatchedChangedItems {
+      add: vec![],
+      update: vec![],
+      delete: vec![],
+    };
+    for (ni, m) in new_items.iter().zip(new_matches) {
+      match m {
+        Some((ei, name)) => changes.update.push((&existing_items[ei], ni, name)),
+        None => changes.add.push(ni),
+      }
+    }
+    changes.delete = existing_items
+      .iter()
+      .zip(existing_matched)
+      .filter(|&(_, matched)| !matched)
+      .map(|(ei, _)| ei)
+      .collect();
+    changes
+  }
+}
+
+/// Same as `ChangedItems`, each update has the name of the key that matched
+/// the pair.
+#[derive(Debug, PartialEq)]
+pub struct MatchedChangedItems<'a, TE, TN>
+where
+  TE: 'a,
+  TN: 'a,
+{
+  pub add: Vec<&'a TN>,
+  pub update: Vec<(&'a TE, &'a TN, &'static str)>,
+  pub delete: Vec<&'a TE>,
+}
+
+impl<'a, TE, TN> MatchedChangedItems<'a, TE, TN> {
+  pub fn into_changed_items(self) -> ChangedItems<'a, TE, TN> {
+    ChangedItems {
+      add: self.add,
+      update: self
+        .update
+        .into_iter()
+        .map(|(ei, ni, _)| (ei, ni))
+        .collect(),
+      delete: self.delete,
+    }
+  }
+}
@@ -0,0 +1,86 @@
+//! Keys borrowed from the items, so comparing `String` keys doesn't clone
+//! them.
+//!
+//! The list functions extract keys through `&'a T: HasItemKey<K>`, so they
+//! take either flavour: an owned `T: HasItemKey<K>`, or a borrowed
+//! `&'a T: HasItemKey<&'a K>`. `#[derive(HasItemKeyRef)]` implements both
+//! `HasItemKeyRef<K>` and the borrowed `HasItemKey<&'a K>`. A type with both
+//! flavours needs the key type spelled out, e.g.
+//! `get_changed_items::<&str, _, _>(..)`.
+//!
+//! Types implementing only `HasItemKeyRef`, such as `String` itself, go
+//! through `get_changed_items_ref` and `get_dup_items_ref`. Closure based
+//! APIs such as `GroupByKey` take `HasItemKeyRef::get_item_key_ref` directly.
+
+use std::hash::Hash;
+
+use super::{dup_items_by, hashed_changed_items, ChangedItems};
+
+pub trait HasItemKeyRef<K: ?Sized> {
+  fn get_item_key_ref(&self) -> &K;
+}
+
+impl<T, K> HasItemKeyRef<K> for (usize, T)
+where
+  T: HasItemKeyRef<K>,
+  K: ?Sized,
+{
+  fn get_item_key_ref(&self) -> &K {
+    self.1.get_item_key_ref()
+  }
+}
+
+impl<T, K> HasItemKeyRef<K> for &T
+where
+  T: HasItemKeyRef<K>,
+  K: ?Sized,
+{
+  fn get_item_key_ref(&self) -> &K {
+    (*self).get_item_key_ref()
+  }
+}
+
+impl HasItemKeyRef<i32> for i32 {
+  fn get_item_key_ref(&self) -> &i32 {
+    self
+  }
+}
+
+impl HasItemKeyRef<String> for String {
+  fn get_item_key_ref(&self) -> &String {
+    self
+  }
+}
+
+impl HasItemKeyRef<str> for String {
+  fn get_item_key_ref(&self) -> &str {
+    self
+  }
+}
+
+/// Same as `get_changed_items_hashed` with borrowed keys.
+pub fn get_changed_items_ref<'a, K, TE, TN>(
+  existing_items: &'a [TE],
+  new_items: &'a [TN],
+) -> ChangedItems<'a, TE, TN>
+where
+  K: Hash + Eq + ?Sized + 'a,
+  TE: HasItemKeyRef<K> + 'a,
+  TN: HasItemKeyRef<K> + 'a,
+{
+  hashed_changed_items(
+    existing_items,
+    new_items,
+    HasItemKeyRef::get_item_key_ref,
+    HasItemKeyRef::get_item_key_ref,
+  )
+}
+
+/// Same as `get_dup_items` with borrowed keys.
+pub fn get_dup_items_ref<'a, K, T>(items: &'a [T]) -> Vec<(&'a K, Vec<(usize, &'a T)>)>
+where
+  K: PartialEq + ?Sized + 'a,
+  T: HasItemKeyRef<K> + 'a,
+{
+  dup_items_by(items, HasItemKeyRef::get_item_key_ref)
+}
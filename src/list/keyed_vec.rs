@@ -0,0 +1,278 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::{hashed_changed_items, ChangedItems, HasItemKey};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyMode {
+  /// At most 1 item per key.
+  Unique,
+  /// Any number of items per key.
+  Multi,
+}
+
+/// A list that keeps insertion order and an index of its item keys.
+///
+/// Removed items leave an empty slot behind, the slots are compacted once
+/// they outnumber the items.
+#[derive(Debug, Clone)]
+pub struct KeyedVec<K, T>
+where
+  K: Hash + Eq,
+{
+  mode: KeyMode,
+  slots: Vec<Option<T>>,
+  // slot positions of each key, in insertion order
+  index: HashMap<K, Vec<usize>>,
+  len: usize,
+}
+
+impl<K, T> Default for KeyedVec<K, T>
+where
+  K: Hash + Eq,
+  T: HasItemKey<K>,
+{
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<K, T> KeyedVec<K, T>
+where
+  K: Hash + Eq,
+  T: HasItemKey<K>,
+{
+  pub fn new() -> Self {
+    Self::with_mode(KeyMode::Unique)
+  }
+
+  pub fn with_mode(mode: KeyMode) -> Self {
+    KeyedVec {
+      mode,
+      slots: vec![],
+      index: HashMap::new(),
+      len: 0,
+    }
+  }
+
+  pub fn mode(&self) -> KeyMode {
+    self.mode
+  }
+
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  pub fn contains_key<Q>(&self, key: &Q) -> bool
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    self.index.contains_key(key)
+  }
+
+  /// Returns the first item with the key.
+  pub fn get<Q>(&self, key: &Q) -> Option<&T>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    self
+      .index
+      .get(key)
+      .map(|positions| self.slots[positions[0]].as_ref().unwrap())
+  }
+
+  /// Changes the first item with the key in place, `None` if there is no
+  /// such item. The item is re-indexed if `f` changes its key. In
+  /// `KeyMode::Unique`, if another item already has the new key, the changed
+  /// item is removed and returned as the error.
+  pub fn update<Q, F>(&mut self, key: &Q, f: F) -> Option<Result<(), T>>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    F: FnOnce(&mut T),
+  {
+    let pos = self.index.get(key)?[0];
+    let new_key = {
+      let item = self.slots[pos].as_mut().unwrap();
+      f(item);
+      item.get_item_key()
+    };
+    if new_key.borrow() == key {
+      return Some(Ok(()));
+    }
+
+    let positions = self.index.get_mut(key).unwrap();
+    positions.remove(0);
+    if positions.is_empty() {
+      self.index.remove(key);
+    }
+    if self.mode == KeyMode::Unique && self.index.contains_key::<K>(&new_key) {
+      let item = self.take_slot(pos);
+      self.compact();
+      return Some(Err(item));
+    }
+    let positions = self.index.entry(new_key).or_default();
+    let at = positions.binary_search(&pos).unwrap_err();
+    positions.insert(at, pos);
+    Some(Ok(()))
+  }
+
+  /// Returns all items with the key, in insertion order.
+  pub fn get_all<Q>(&self, key: &Q) -> Vec<&T>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    match self.index.get(key) {
+      Some(positions) => positions
+        .iter()
+        .map(|&pos| self.slots[pos].as_ref().unwrap())
+        .collect(),
+      None => vec![],
+    }
+  }
+
+  /// Appends the item. In `KeyMode::Unique`, fails with the item if its key
+  /// already exists.
+  pub fn push(&mut self, item: T) -> Result<(), T> {
+    let key = item.get_item_key();
+    if self.mode == KeyMode::Unique && self.index.contains_key(&key) {
+      return Err(item);
+    }
+    self.index.entry(key).or_default().push(self.slots.len());
+    self.slots.push(Some(item));
+    self.len += 1;
+    Ok(())
+  }
+
+  /// Replaces the first item with the same key in place and returns it, or
+  /// appends the item.
+  pub fn upsert(&mut self, item: T) -> Option<T> {
+    let key = item.get_item_key();
+    match self.index.get(&key) {
+      Some(positions) => self.slots[positions[0]].replace(item),
+      None => {
+        self.index.insert(key, vec![self.slots.len()]);
+        self.slots.push(Some(item));
+        self.len += 1;
+        None
+      }
+    }
+  }
+
+  /// Removes the first item with the key.
+  pub fn remove<Q>(&mut self, key: &Q) -> Option<T>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    let pos = {
+      let positions = self.index.get_mut(key)?;
+      let pos = positions.remove(0);
+      if positions.is_empty() {
+        self.index.remove(key);
+      }
+      pos
+    };
+    let item = self.take_slot(pos);
+    self.compact();
+    Some(item)
+  }
+
+  /// Removes all items with the key.
+  pub fn remove_all<Q>(&mut self, key: &Q) -> Vec<T>
+  where
+    K: Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+  {
+    let items = match self.index.remove(key) {
+      Some(positions) => positions
+        .into_iter()
+        .map(|pos| self.take_slot(pos))
+        .collect(),
+      None => vec![],
+    };
+    self.compact();
+    items
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &T> {
+    self.slots.iter().filter_map(Option::as_ref)
+  }
+
+  pub fn into_vec(self) -> Vec<T> {
+    self.slots.into_iter().flatten().collect()
+  }
+
+  /// `get_changed_items` with the items of this list as the existing items.
+  pub fn get_changed_items<'a, TN>(&'a self, new_items: &'a [TN]) -> ChangedItems<'a, T, TN>
+  where
+    &'a TN: HasItemKey<K>,
+  {
+    hashed_changed_items(
+      self.iter(),
+      new_items,
+      |ei| ei.get_item_key(),
+      |ni| ni.get_item_key(),
+    )
+  }
+
+  /// Same as `get_dup_items`, indexes are positions in `iter()`.
+  pub fn get_dup_items(&self) -> Vec<(&K, Vec<(usize, &T)>)> {
+    let item_idxs = self.item_positions();
+    let mut dups: Vec<_> = self
+      .index
+      .iter()
+      .filter(|&(_, positions)| positions.len() > 1)
+      .map(|(key, positions)| {
+        (
+          key,
+          positions
+            .iter()
+            .map(|&pos| (item_idxs[pos], self.slots[pos].as_ref().unwrap()))
+            .collect::<Vec<_>>(),
+        )
+      })
+      .collect();
+    dups.sort_by_key(|(_, items)| items[0].0);
+    dups
+  }
+
+  // position in `iter()` of each slot
+  fn item_positions(&self) -> Vec<usize> {
+    let mut positions = Vec::with_capacity(self.slots.len());
+    let mut next = 0;
+    for slot in &self.slots {
+      positions.push(next);
+      if slot.is_some() {
+        next += 1;
+      }
+    }
+    positions
+  }
+
+  fn take_slot(&mut self, pos: usize) -> T {
+    self.len -= 1;
+    self.slots[pos].take().unwrap()
+  }
+
+  fn compact(&mut self) {
+    if self.slots.len() - self.len <= self.len {
+      return;
+    }
+    let new_positions = self.item_positions();
+    self.slots.retain(Option::is_some);
+    for positions in self.index.values_mut() {
+      for pos in positions.iter_mut() {
+        *pos = new_positions[*pos];
+      }
+    }
+  }
+}
@@ -0,0 +1,133 @@
+use std::hash::Hash;
+
+use super::HasItemKey;
+use crate::key_index::KeyIndex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Merge3Kind {
+  /// Neither side changed the item.
+  Unchanged,
+  /// Only the local side changed the item.
+  Local,
+  /// Only the remote side changed the item.
+  Remote,
+  /// Both sides made the same change.
+  Both,
+  /// Both sides made different changes.
+  Conflict,
+}
+
+/// Outcome of a conflict, returned by the resolver of `merge3_with`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Merge3Resolution {
+  Base,
+  Local,
+  Remote,
+  /// Leave the conflict unresolved.
+  Unresolved,
+}
+
+/// One key of a three-way merge. `None` means the item doesn't exist on that
+/// side.
+#[derive(Debug, PartialEq)]
+pub struct Merge3Item<'a, K, T>
+where
+  T: 'a,
+{
+  pub key: K,
+  pub kind: Merge3Kind,
+  pub base: Option<&'a T>,
+  pub local: Option<&'a T>,
+  pub remote: Option<&'a T>,
+  /// The merged item, `Some(None)` if the item is deleted, `None` if the
+  /// conflict is unresolved.
+  pub merged: Option<Option<&'a T>>,
+}
+
+impl<'a, K, T> Merge3Item<'a, K, T> {
+  pub fn is_resolved(&self) -> bool {
+    self.merged.is_some()
+  }
+}
+
+/// Three-way merge of keyed lists, conflicts are left unresolved.
+///
+/// Items are compared with `PartialEq`. The result follows the order of
+/// `base`, then the keys only in `local`, then the keys only in `remote`.
+/// Duplicated keys are not supported, the last item of each key is used.
+pub fn merge3<'a, K, T>(base: &'a [T], local: &'a [T], remote: &'a [T]) -> Vec<Merge3Item<'a, K, T>>
+where
+  K: Hash + Eq,
+  T: PartialEq + 'a,
+  &'a T: HasItemKey<K>,
+{
+  merge3_with(base, local, remote, |_| Merge3Resolution::Unresolved)
+}
+
+/// Same as `merge3`, with a resolver closure called for each conflict.
+pub fn merge3_with<'a, K, T, F>(
+  base: &'a [T],
+  local: &'a [T],
+  remote: &'a [T],
+  mut resolve: F,
+) -> Vec<Merge3Item<'a, K, T>>
+where
+  K: Hash + Eq,
+  T: PartialEq + 'a,
+  &'a T: HasItemKey<K>,
+  F: FnMut(&Merge3Item<'a, K, T>) -> Merge3Resolution,
+{
+  let mut index = KeyIndex::new();
+  let mut items: Vec<[Option<&T>; 3]> = vec![];
+  for (side, list) in [base, local, remote].iter().enumerate() {
+    for item in list.iter() {
+      let pos = index.insert(item.get_item_key());
+      if pos == items.len() {
+        items.push([None; 3]);
+      }
+      items[pos][side] = Some(item);
+    }
+  }
+
+  index
+    .into_keys()
+    .into_iter()
+    .zip(items)
+    .map(|(key, [base, local, remote])| {
+      let kind = if local == remote {
+        if local == base {
+          Merge3Kind::Unchanged
+        } else {
+          Merge3Kind::Both
+        }
+      } else if local == base {
+        Merge3Kind::Remote
+      } else if remote == base {
+        Merge3Kind::Local
+      } else {
+        Merge3Kind::Conflict
+      };
+      let mut item = Merge3Item {
+        key,
+        kind,
+        base,
+        local,
+        remote,
+        merged: match kind {
+          Merge3Kind::Unchanged | Merge3Kind::Local | Merge3Kind::Both => Some(local),
+          Merge3Kind::Remote => Some(remote),
+          Merge3Kind::Conflict => None,
+        },
+      };
+      if kind == Merge3Kind::Conflict {
+        item.merged = match resolve(&item) {
+          Merge3Resolution::Base => Some(base),
+          Merge3Resolution::Local => Some(local),
+          Merge3Resolution::Remote => Some(remote),
+          Merge3Resolution::Unresolved => None,
+        };
+      }
+      item
+    })
+    .collect()
+}
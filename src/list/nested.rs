@@ -0,0 +1,102 @@
+use std::hash::Hash;
+
+use super::{get_changed_items_hashed, ChangedItems, HasItemKey};
+
+/// A parent item with a child collection, e.g. an order and its lines. A
+/// parent with several collections implements it once per child type.
+pub trait HasChildItems<C> {
+  fn get_child_items(&self) -> &[C];
+}
+
+/// Diffs the child collections of a parent along with it, e.g. the lines and
+/// the payments of an order. `existing` is `None` for an added parent, `new`
+/// is `None` for a deleted one.
+pub trait DiffChildren<'a, TN = Self> {
+  /// Changes of the child collections, e.g. a tuple with one
+  /// `child_changed_items` per collection, or `nested_child_changed_items`
+  /// for children with collections of their own.
+  type Changes;
+
+  fn diff_children(existing: Option<&'a Self>, new: Option<&'a TN>) -> Self::Changes;
+}
+
+/// Changes of the parent items, each with the changes of its children.
+pub struct NestedChangedItems<'a, TE, TN, C>
+where
+  TE: 'a,
+  TN: 'a,
+{
+  pub add: Vec<(&'a TN, C)>,
+  pub update: Vec<(&'a TE, &'a TN, C)>,
+  pub delete: Vec<(&'a TE, C)>,
+}
+
+impl<'a, TE, TN> ChangedItems<'a, TE, TN> {
+  /// Attaches the `DiffChildren` changes of each parent. The parents are not
+  /// diffed again.
+  pub fn nested_changes(&self) -> NestedChangedItems<'a, TE, TN, TE::Changes>
+  where
+    TE: DiffChildren<'a, TN>,
+  {
+    self.with_child_changes(TE::diff_children)
+  }
+
+  /// Same as `nested_changes`, with the child changes of each parent returned
+  /// by `child_changes`.
+  pub fn with_child_changes<C, F>(&self, child_changes: F) -> NestedChangedItems<'a, TE, TN, C>
+  where
+    F: Fn(Option<&'a TE>, Option<&'a TN>) -> C,
+  {
+    NestedChangedItems {
+      add: self
+        .add
+        .iter()
+        .map(|&ni| (ni, child_changes(None, Some(ni))))
+        .collect(),
+      update: self
+        .update
+        .iter()
+        .map(|&(ei, ni)| (ei, ni, child_changes(Some(ei), Some(ni))))
+        .collect(),
+      delete: self
+        .delete
+        .iter()
+        .map(|&ei| (ei, child_changes(Some(ei), None)))
+        .collect(),
+    }
+  }
+}
+
+/// Diffs the `C` children of a parent. All children of an added parent are
+/// added, all children of a deleted parent are deleted.
+pub fn child_changed_items<'a, KC, C, TE, TN>(
+  existing: Option<&'a TE>,
+  new: Option<&'a TN>,
+) -> ChangedItems<'a, C, C>
+where
+  KC: Hash + Eq,
+  TE: HasChildItems<C>,
+  TN: HasChildItems<C>,
+  &'a C: HasItemKey<KC>,
+{
+  get_changed_items_hashed(
+    existing.map_or(&[][..], HasChildItems::get_child_items),
+    new.map_or(&[][..], HasChildItems::get_child_items),
+  )
+}
+
+/// Same as `child_changed_items` for children with collections of their own,
+/// e.g. the variants of a product and their images.
+pub fn nested_child_changed_items<'a, KC, C, TE, TN>(
+  existing: Option<&'a TE>,
+  new: Option<&'a TN>,
+) -> NestedChangedItems<'a, C, C, C::Changes>
+where
+  KC: Hash + Eq,
+  C: DiffChildren<'a>,
+  TE: HasChildItems<C>,
+  TN: HasChildItems<C>,
+  &'a C: HasItemKey<KC>,
+{
+  child_changed_items(existing, new).nested_changes()
+}
@@ -0,0 +1,183 @@
+//! Normalized `String` keys, e.g. to match SKUs that differ in case or
+//! surrounding whitespace.
+
+use super::{hashed_changed_items, ChangedItems, HasItemKey};
+
+pub type NormalizeFn<'f> = Box<dyn Fn(String) -> String + 'f>;
+
+/// A chain of key transformations, applied in the order they are added.
+#[derive(Default)]
+pub struct KeyNormalizer<'f> {
+  steps: Vec<NormalizeFn<'f>>,
+}
+
+impl<'f> KeyNormalizer<'f> {
+  pub fn new() -> Self {
+    KeyNormalizer { steps: vec![] }
+  }
+
+  /// Removes leading and trailing whitespace.
+  pub fn trim(self) -> Self {
+    self.custom(|key| key.trim().to_string())
+  }
+
+  /// Lowercases the key. This is not full Unicode case folding, e.g.
+  /// "STRASSE" and "straße" stay different, see `casefold`.
+  pub fn lowercase(self) -> Self {
+    self.custom(|key| key.to_lowercase())
+  }
+
+  /// Unicode compatibility normalization (NFKC), e.g. full width letters
+  /// become ASCII.
+  #[cfg(feature = "unicode-normalization")]
+  pub fn nfkc(self) -> Self {
+    use unicode_normalization::UnicodeNormalization;
+    self.custom(|key| key.nfkc().collect())
+  }
+
+  /// Full Unicode case folding, e.g. "STRASSE" and "straße" both become
+  /// "strasse".
+  #[cfg(feature = "unicode-normalization")]
+  pub fn casefold(self) -> Self {
+    self.custom(|key| caseless::default_case_fold_str(&key))
+  }
+
+  pub fn custom<F>(mut self, f: F) -> Self
+  where
+    F: Fn(String) -> String + 'f,
+  {
+    self.steps.push(Box::new(f));
+    self
+  }
+
+  pub fn normalize(&self, key: String) -> String {
+    self.steps.iter().fold(key, |key, step| step(key))
+  }
+
+  /// Pairs each item with its original and normalized keys.
+  pub fn wrap<'a, T>(&self, items: &'a [T]) -> Vec<NormalizedItem<'a, T>>
+  where
+    T: HasItemKey<String>,
+  {
+    items
+      .iter()
+      .map(|item| {
+        let key = item.get_item_key();
+        NormalizedItem {
+          item,
+          normalized_key: self.normalize(key.clone()),
+          key,
+        }
+      })
+      .collect()
+  }
+
+  /// Same as `get_changed_items_hashed`, comparing the normalized keys.
+  pub fn get_changed_items<'a, TE, TN>(
+    &self,
+    existing_items: &'a [TE],
+    new_items: &'a [TN],
+  ) -> NormalizedChangedItems<'a, TE, TN>
+  where
+    TE: HasItemKey<String>,
+    TN: HasItemKey<String>,
+  {
+    let existing = self.wrap(existing_items);
+    let new = self.wrap(new_items);
+    // diff the positions, then move the wrapped items into the result
+    let existing_idxs: Vec<usize> = (0..existing.len()).collect();
+    let new_idxs: Vec<usize> = (0..new.len()).collect();
+    let changes = hashed_changed_items(
+      &existing_idxs,
+      &new_idxs,
+      |&i| existing[i].normalized_key.as_str(),
+      |&i| new[i].normalized_key.as_str(),
+    );
+    // the extra existing items of a key are deleted once per new item with
+    // the key, only the last delete moves the item
+    let mut deletes = vec![0; existing.len()];
+    for &&i in &changes.delete {
+      deletes[i] += 1;
+    }
+    let mut existing: Vec<_> = existing.into_iter().map(Some).collect();
+    let mut new: Vec<_> = new.into_iter().map(Some).collect();
+    NormalizedChangedItems {
+      add: changes
+        .add
+        .into_iter()
+        .map(|&i| new[i].take().unwrap())
+        .collect(),
+      update: changes
+        .update
+        .into_iter()
+        .map(|(&ei, &ni)| (existing[ei].take().unwrap(), new[ni].take().unwrap()))
+        .collect(),
+      delete: changes
+        .delete
+        .into_iter()
+        .map(|&i| {
+          deletes[i] -= 1;
+          if deletes[i] == 0 {
+            existing[i].take().unwrap()
+          } else {
+            existing[i].clone().unwrap()
+          }
+        })
+        .collect(),
+    }
+  }
+}
+
+/// An item with its original key, its `HasItemKey` impl returns the
+/// normalized key.
+#[derive(Debug, PartialEq)]
+pub struct NormalizedItem<'a, T>
+where
+  T: 'a,
+{
+  pub item: &'a T,
+  pub key: String,
+  pub normalized_key: String,
+}
+
+impl<'a, T> Clone for NormalizedItem<'a, T> {
+  fn clone(&self) -> Self {
+    NormalizedItem {
+      item: self.item,
+      key: self.key.clone(),
+      normalized_key: self.normalized_key.clone(),
+    }
+  }
+}
+
+impl<'a, T> HasItemKey<String> for NormalizedItem<'a, T> {
+  fn get_item_key(&self) -> String {
+    self.normalized_key.clone()
+  }
+}
+
+/// Result of `KeyNormalizer::get_changed_items`, with the original keys.
+#[derive(Debug, PartialEq)]
+pub struct NormalizedChangedItems<'a, TE, TN>
+where
+  TE: 'a,
+  TN: 'a,
+{
+  pub add: Vec<NormalizedItem<'a, TN>>,
+  pub update: Vec<(NormalizedItem<'a, TE>, NormalizedItem<'a, TN>)>,
+  pub delete: Vec<NormalizedItem<'a, TE>>,
+}
+
+impl<'a, TE, TN> NormalizedChangedItems<'a, TE, TN> {
+  pub fn into_changed_items(self) -> ChangedItems<'a, TE, TN> {
+    ChangedItems {
+      add: self.add.into_iter().map(|ni| ni.item).collect(),
+      update: self
+        .update
+        .into_iter()
+        .map(|(ei, ni)| (ei.item, ni.item))
+        .collect(),
+      delete: self.delete.into_iter().map(|ei| ei.item).collect(),
+    }
+  }
+}
@@ -0,0 +1,117 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+use super::HasItemKey;
+
+/// Changes between two ordered lists.
+///
+/// Indexes refer to the position in the existing list (`from`) and in the
+/// new list (`to`).
+pub struct OrderedChangedItems<'a, TE, TN>
+where
+  TE: 'a,
+  TN: 'a,
+{
+  /// `(to, item)`, in new list order.
+  pub add: Vec<(usize, &'a TN)>,
+  /// `(from, existing, to, new)`, in new list order.
+  pub update: Vec<(usize, &'a TE, usize, &'a TN)>,
+  /// `(from, item)`, in existing list order.
+  pub delete: Vec<(usize, &'a TE)>,
+  /// `(from, to)` of the updated items that have to be moved, in new list
+  /// order.
+  ///
+  /// The updated items not listed here keep their relative order, and there
+  /// is no shorter list of moves that produces the new order.
+  pub moves: Vec<(usize, usize)>,
+}
+
+/// Like `get_changed_items`, but also reports the items that changed position.
+///
+/// If a key appears more than once, its occurrences are matched in order: the
+/// first existing item with the first new item, and so on. The remaining
+/// occurrences are added or deleted.
+pub fn get_ordered_changed_items<'a, K, TE, TN>(
+  existing_items: &'a [TE],
+  new_items: &'a [TN],
+) -> OrderedChangedItems<'a, TE, TN>
+where
+  K: Hash + Eq,
+  TE: 'a,
+  &'a TE: HasItemKey<K>,
+  TN: 'a,
+  &'a TN: HasItemKey<K>,
+{
+  let mut index: HashMap<K, VecDeque<usize>> = HashMap::new();
+  for (from, ei) in existing_items.iter().enumerate() {
+    index.entry(ei.get_item_key()).or_default().push_back(from);
+  }
+
+  let mut result = OrderedChangedItems {
+    add: vec![],
+    update: vec![],
+    delete: vec![],
+    moves: vec![],
+  };
+
+  let mut matched = vec![false; existing_items.len()];
+  for (to, ni) in new_items.iter().enumerate() {
+    match index
+      .get_mut(&ni.get_item_key())
+      .and_then(|positions| positions.pop_front())
+    {
+      Some(from) => {
+        matched[from] = true;
+        result.update.push((from, &existing_items[from], to, ni));
+      }
+      None => result.add.push((to, ni)),
+    }
+  }
+
+  result.delete = existing_items
+    .iter()
+    .enumerate()
+    .filter(|&(from, _)| !matched[from])
+    .collect();
+
+  let from_positions: Vec<usize> = result.update.iter().map(|u| u.0).collect();
+  let mut stays = longest_increasing_subsequence(&from_positions)
+    .into_iter()
+    .peekable();
+  for (i, &(from, _, to, _)) in result.update.iter().enumerate() {
+    if stays.peek() == Some(&i) {
+      stays.next();
+    } else {
+      result.moves.push((from, to));
+    }
+  }
+
+  result
+}
+
+// Returns the indexes of a longest strictly increasing subsequence of `values`.
+fn longest_increasing_subsequence(values: &[usize]) -> Vec<usize> {
+  // tails[l]: index of the smallest tail of all increasing subsequences of length l + 1
+  let mut tails: Vec<usize> = vec![];
+  let mut prev: Vec<Option<usize>> = Vec::with_capacity(values.len());
+  for (i, &value) in values.iter().enumerate() {
+    let len = match tails.binary_search_by(|&t| values[t].cmp(&value)) {
+      Ok(len) | Err(len) => len,
+    };
+    prev.push(if len > 0 { Some(tails[len - 1]) } else { None });
+    if len == tails.len() {
+      tails.push(i);
+    } else {
+      tails[len] = i;
+    }
+  }
+
+  let mut indexes = vec![];
+  let mut next = tails.last().cloned();
+  while let Some(i) = next {
+    indexes.push(i);
+    next = prev[i];
+  }
+  indexes.reverse();
+  indexes
+}
@@ -0,0 +1,86 @@
+//! Parallel versions of the list functions, with the `rayon` feature.
+//!
+//! Keys are extracted in parallel, the results are identical to the serial
+//! versions, including their order.
+
+use std::hash::Hash;
+use std::vec::IntoIter;
+
+use rayon::prelude::*;
+
+use super::{keyed_changed_items, ChangedItems, DedupKeyList, HasItemKey};
+use crate::key_index::group_keyed;
+
+/// Parallel `get_changed_items`.
+pub fn par_get_changed_items<'a, K, TE, TN>(
+  existing_items: &'a [TE],
+  new_items: &'a [TN],
+) -> ChangedItems<'a, TE, TN>
+where
+  K: Hash + Eq + Send,
+  TE: Sync + 'a,
+  &'a TE: HasItemKey<K>,
+  TN: Sync + 'a,
+  &'a TN: HasItemKey<K>,
+{
+  let (existing_keys, new_keys): (Vec<K>, Vec<K>) = rayon::join(
+    || {
+      existing_items
+        .par_iter()
+        .map(|i| i.get_item_key())
+        .collect()
+    },
+    || new_items.par_iter().map(|i| i.get_item_key()).collect(),
+  );
+  keyed_changed_items(
+    existing_items.iter().zip(existing_keys),
+    new_items.iter().zip(new_keys),
+  )
+}
+
+/// Parallel `get_dup_items`, keys are hashed instead of compared one by one.
+pub fn par_get_dup_items<'a, K, T>(items: &'a [T]) -> Vec<(K, Vec<(usize, &'a T)>)>
+where
+  K: Hash + Eq + Send,
+  T: Sync + 'a,
+  &'a T: HasItemKey<K>,
+{
+  let keys: Vec<K> = items.par_iter().map(|i| i.get_item_key()).collect();
+  group_keyed(items.iter().enumerate().zip(keys))
+    .into_iter()
+    .filter(|(_, items)| items.len() > 1)
+    .collect()
+}
+
+/// Parallel `GroupByKey::group_by_key`.
+pub fn par_group_by_key<T, I, F>(items: Vec<T>, f: F) -> IntoIter<(I, Vec<T>)>
+where
+  T: Sync,
+  I: Hash + Eq + Send,
+  F: Fn(&T) -> I + Sync,
+{
+  let keys: Vec<I> = items.par_iter().map(&f).collect();
+  group_keyed(items.into_iter().zip(keys)).into_iter()
+}
+
+/// Parallel `dedup_map`.
+pub fn par_dedup_map<'a, I, F, R, RI>(items: &'a [I], f: F) -> Vec<RI>
+where
+  I: Sync,
+  F: Fn(&'a I) -> R + Sync,
+  R: Into<DedupKeyList<RI>>,
+  RI: PartialEq<RI> + Ord + Send,
+{
+  let mut all_items: Vec<RI> = items
+    .par_iter()
+    .map(|item| -> DedupKeyList<RI> { f(item).into() })
+    .flat_map_iter(|kl| {
+      let mut items = vec![];
+      kl.move_to(&mut items);
+      items
+    })
+    .collect();
+  all_items.par_sort();
+  all_items.dedup();
+  all_items
+}
@@ -0,0 +1,145 @@
+use super::{Batch, BatchPlanner, ChangedItems, HasItemKey};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+  Insert,
+  Update,
+  Delete,
+}
+
+pub(super) fn check_order(name: &str, order: &[ChangeKind; 3]) {
+  for kind in &[ChangeKind::Insert, ChangeKind::Update, ChangeKind::Delete] {
+    if !order.contains(kind) {
+      panic!("{} order is missing {:?}", name, kind);
+    }
+  }
+}
+
+/// Target of a `ChangedItems` changeset, e.g. a database table or a remote API.
+///
+/// Each hook receives one batch, see `Reconciler::batch_size` and
+/// `Reconciler::max_weight`.
+pub trait ChangeSink<TE, TN> {
+  type Error;
+
+  fn insert(&mut self, items: &[&TN]) -> Result<(), Self::Error>;
+  fn update(&mut self, items: &[(&TE, &TN)]) -> Result<(), Self::Error>;
+  fn delete(&mut self, items: &[&TE]) -> Result<(), Self::Error>;
+}
+
+#[derive(Debug)]
+pub struct ApplyFailure<K, E> {
+  pub kind: ChangeKind,
+  /// Keys of all items in the failed batch.
+  pub keys: Vec<K>,
+  pub error: E,
+}
+
+#[derive(Debug)]
+pub struct ApplyReport<K, E> {
+  pub applied: Vec<(ChangeKind, K)>,
+  pub failed: Vec<ApplyFailure<K, E>>,
+  /// Items not sent to the sink because of `Reconciler::stop_on_error`.
+  pub skipped: Vec<(ChangeKind, K)>,
+}
+
+impl<K, E> ApplyReport<K, E> {
+  pub fn is_ok(&self) -> bool {
+    self.failed.is_empty()
+  }
+}
+
+/// Applies `ChangedItems` to a `ChangeSink`, one `BatchPlan` batch per hook
+/// call.
+///
+/// By default items are inserted, then updated, then deleted, one item per
+/// hook call, and failures don't stop the remaining items.
+pub struct Reconciler<'f, TE, TN> {
+  planner: BatchPlanner<'f, TE, TN>,
+  stop_on_error: bool,
+}
+
+impl<'f, TE, TN> Default for Reconciler<'f, TE, TN> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<'f, TE, TN> Reconciler<'f, TE, TN> {
+  pub fn new() -> Self {
+    Self::with_planner(BatchPlanner::new(1))
+  }
+
+  pub fn with_planner(planner: BatchPlanner<'f, TE, TN>) -> Self {
+    Reconciler {
+      planner,
+      stop_on_error: false,
+    }
+  }
+
+  pub fn order(mut self, order: [ChangeKind; 3]) -> Self {
+    self.planner = self.planner.order(order);
+    self
+  }
+
+  pub fn batch_size(mut self, batch_size: usize) -> Self {
+    self.planner = self.planner.max_items(batch_size);
+    self
+  }
+
+  /// See `BatchPlanner::max_weight`.
+  pub fn max_weight<FE, FN>(mut self, limit: usize, existing: FE, new: FN) -> Self
+  where
+    FE: Fn(&TE) -> usize + 'f,
+    FN: Fn(&TN) -> usize + 'f,
+  {
+    self.planner = self.planner.max_weight(limit, existing, new);
+    self
+  }
+
+  pub fn stop_on_error(mut self, stop_on_error: bool) -> Self {
+    self.stop_on_error = stop_on_error;
+    self
+  }
+
+  pub fn apply<'a, K, S>(
+    &self,
+    changes: &ChangedItems<'a, TE, TN>,
+    sink: &mut S,
+  ) -> ApplyReport<K, S::Error>
+  where
+    &'a TE: HasItemKey<K>,
+    &'a TN: HasItemKey<K>,
+    S: ChangeSink<TE, TN>,
+  {
+    let mut report = ApplyReport {
+      applied: vec![],
+      failed: vec![],
+      skipped: vec![],
+    };
+
+    for batch in self.planner.plan(changes).batches {
+      let kind = batch.kind();
+      let keys: Vec<K> = match batch {
+        Batch::Insert(ref items) => items.iter().map(|ni| ni.get_item_key()).collect(),
+        Batch::Update(ref items) => items.iter().map(|(ei, _)| ei.get_item_key()).collect(),
+        Batch::Delete(ref items) => items.iter().map(|ei| ei.get_item_key()).collect(),
+      };
+      if self.stop_on_error && !report.failed.is_empty() {
+        report.skipped.extend(keys.into_iter().map(|k| (kind, k)));
+        continue;
+      }
+      let result = match batch {
+        Batch::Insert(ref items) => sink.insert(items),
+        Batch::Update(ref items) => sink.update(items),
+        Batch::Delete(ref items) => sink.delete(items),
+      };
+      match result {
+        Ok(_) => report.applied.extend(keys.into_iter().map(|k| (kind, k))),
+        Err(error) => report.failed.push(ApplyFailure { kind, keys, error }),
+      }
+    }
+
+    report
+  }
+}
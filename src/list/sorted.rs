@@ -0,0 +1,201 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+
+use super::{HasItemKey, ListSide};
+
+#[derive(Debug, PartialEq)]
+pub enum SortedChange<TE, TN> {
+  Add(TN),
+  Update(TE, TN),
+  Delete(TE),
+}
+
+/// An item key is smaller than the key of the item before it.
+#[derive(Debug, PartialEq)]
+pub struct OutOfOrderError<K> {
+  pub side: ListSide,
+  /// Position of the item in its input.
+  pub index: usize,
+  pub key: K,
+  pub previous_key: K,
+}
+
+impl<K: fmt::Debug> fmt::Display for OutOfOrderError<K> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "{:?} items are not sorted by key: item {} has key {:?} after key {:?}",
+      self.side, self.index, self.key, self.previous_key
+    )
+  }
+}
+
+impl<K: fmt::Debug> Error for OutOfOrderError<K> {}
+
+/// Compares two inputs sorted by item key, without collecting them.
+///
+/// Yields the same changes as `get_changed_items`, ordered by key, except
+/// that the extra existing items of a key are deleted once, not once per new
+/// item with the key. Iteration stops after an `OutOfOrderError`.
+pub fn get_sorted_changes<K, IE, IN>(
+  existing_items: IE,
+  new_items: IN,
+) -> SortedChanges<K, IE::IntoIter, IN::IntoIter>
+where
+  K: Ord,
+  IE: IntoIterator,
+  IN: IntoIterator,
+  IE::Item: HasItemKey<K>,
+  IN::Item: HasItemKey<K>,
+{
+  SortedChanges {
+    existing: SortedInput::new(existing_items.into_iter(), ListSide::Existing),
+    new: SortedInput::new(new_items.into_iter(), ListSide::New),
+    done: false,
+  }
+}
+
+type NextChange<K, TE, TN> = Result<Option<SortedChange<TE, TN>>, OutOfOrderError<K>>;
+
+pub struct SortedChanges<K, IE, IN>
+where
+  IE: Iterator,
+  IN: Iterator,
+{
+  existing: SortedInput<K, IE>,
+  new: SortedInput<K, IN>,
+  done: bool,
+}
+
+impl<K, IE, IN> SortedChanges<K, IE, IN>
+where
+  K: Ord,
+  IE: Iterator,
+  IN: Iterator,
+  IE::Item: HasItemKey<K>,
+  IN::Item: HasItemKey<K>,
+{
+  fn next_change(&mut self) -> NextChange<K, IE::Item, IN::Item> {
+    self.existing.fill()?;
+    self.new.fill()?;
+
+    let ord = match (self.existing.peek_key(), self.new.peek_key()) {
+      (None, None) => return Ok(None),
+      (Some(_), None) => Ordering::Less,
+      (None, Some(_)) => Ordering::Greater,
+      (Some(existing_key), Some(new_key)) => existing_key.cmp(new_key),
+    };
+
+    let change = match ord {
+      Ordering::Less => SortedChange::Delete(self.existing.take()),
+      Ordering::Greater => SortedChange::Add(self.new.take()),
+      Ordering::Equal => {
+        // If more than 1 existing items match, the rest are smaller than the
+        // next new key and get deleted.
+        let ei = self.existing.take();
+        let mut ni = self.new.take();
+        // If more than 1 new items match, the last one wins.
+        loop {
+          self.new.fill()?;
+          if self.new.peek_key().is_some() && self.new.peek_key() == self.new.last.as_ref() {
+            ni = self.new.take();
+          } else {
+            break;
+          }
+        }
+        SortedChange::Update(ei, ni)
+      }
+    };
+    Ok(Some(change))
+  }
+}
+
+impl<K, IE, IN> Iterator for SortedChanges<K, IE, IN>
+where
+  K: Ord,
+  IE: Iterator,
+  IN: Iterator,
+  IE::Item: HasItemKey<K>,
+  IN::Item: HasItemKey<K>,
+{
+  type Item = Result<SortedChange<IE::Item, IN::Item>, OutOfOrderError<K>>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.done {
+      return None;
+    }
+    match self.next_change() {
+      Ok(Some(change)) => Some(Ok(change)),
+      Ok(None) => {
+        self.done = true;
+        None
+      }
+      Err(err) => {
+        self.done = true;
+        Some(Err(err))
+      }
+    }
+  }
+}
+
+// One side of the merge join with 1 item lookahead.
+struct SortedInput<K, I>
+where
+  I: Iterator,
+{
+  iter: I,
+  side: ListSide,
+  next: Option<(K, I::Item)>,
+  // key of the last taken item
+  last: Option<K>,
+  index: usize,
+}
+
+impl<K, I> SortedInput<K, I>
+where
+  K: Ord,
+  I: Iterator,
+  I::Item: HasItemKey<K>,
+{
+  fn new(iter: I, side: ListSide) -> Self {
+    SortedInput {
+      iter,
+      side,
+      next: None,
+      last: None,
+      index: 0,
+    }
+  }
+
+  fn fill(&mut self) -> Result<(), OutOfOrderError<K>> {
+    if self.next.is_some() {
+      return Ok(());
+    }
+    if let Some(item) = self.iter.next() {
+      let key = item.get_item_key();
+      let index = self.index;
+      self.index += 1;
+      if self.last.as_ref().map(|last| key < *last).unwrap_or(false) {
+        return Err(OutOfOrderError {
+          side: self.side,
+          index,
+          key,
+          previous_key: self.last.take().unwrap(),
+        });
+      }
+      self.next = Some((key, item));
+    }
+    Ok(())
+  }
+
+  fn peek_key(&self) -> Option<&K> {
+    self.next.as_ref().map(|(key, _)| key)
+  }
+
+  fn take(&mut self) -> I::Item {
+    let (key, item) = self.next.take().expect("take from an empty sorted input");
+    self.last = Some(key);
+    item
+  }
+}
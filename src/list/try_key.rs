@@ -0,0 +1,140 @@
+//! Diffs of items that may not have a valid key yet, e.g. a missing SKU or an
+//! unparsable id. Items failing `TryHasItemKey` are collected in an `invalid`
+//! bucket instead of aborting the whole diff.
+
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+
+use super::{get_dup_items, keyed_changed_items, ChangedItems};
+use crate::key_index::group_keyed;
+
+pub trait TryHasItemKey<K> {
+  type Error;
+
+  fn try_get_item_key(&self) -> Result<K, Self::Error>;
+}
+
+/// Error of `#[derive(TryHasItemKey)]` when an `Option` key field is `None`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingItemKey {
+  pub field: &'static str,
+}
+
+impl fmt::Display for MissingItemKey {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "item key field `{}` is missing", self.field)
+  }
+}
+
+impl Error for MissingItemKey {}
+
+/// An item without a valid key, and its index in the input list.
+#[derive(Debug, PartialEq)]
+pub struct InvalidItem<'a, T, E>
+where
+  T: 'a,
+{
+  pub index: usize,
+  pub item: &'a T,
+  pub error: E,
+}
+
+type InvalidItems<'a, T, K> = Vec<InvalidItem<'a, T, <T as TryHasItemKey<K>>::Error>>;
+// `(index, (item, key))` of the items with a valid key
+type KeyedItems<'a, T, K> = Vec<(usize, (&'a T, K))>;
+type DupItems<'a, T, K> = Vec<(K, Vec<(usize, &'a T)>)>;
+type KeyGroups<'a, T, K> = Vec<(K, Vec<&'a T>)>;
+
+pub struct TryChangedItems<'a, K, TE, TN>
+where
+  TE: TryHasItemKey<K> + 'a,
+  TN: TryHasItemKey<K> + 'a,
+{
+  /// Changes of the items with a valid key.
+  pub changes: ChangedItems<'a, TE, TN>,
+  pub invalid_existing: InvalidItems<'a, TE, K>,
+  pub invalid_new: InvalidItems<'a, TN, K>,
+}
+
+impl<'a, K, TE, TN> TryChangedItems<'a, K, TE, TN>
+where
+  TE: TryHasItemKey<K> + 'a,
+  TN: TryHasItemKey<K> + 'a,
+{
+  pub fn has_invalid(&self) -> bool {
+    !self.invalid_existing.is_empty() || !self.invalid_new.is_empty()
+  }
+}
+
+// Splits the items into `(index, (item, key))` and the invalid items.
+fn partition_keys<K, T>(items: &[T]) -> (KeyedItems<'_, T, K>, InvalidItems<'_, T, K>)
+where
+  T: TryHasItemKey<K>,
+{
+  let mut valid = vec![];
+  let mut invalid = vec![];
+  for (index, item) in items.iter().enumerate() {
+    match item.try_get_item_key() {
+      Ok(key) => valid.push((index, (item, key))),
+      Err(error) => invalid.push(InvalidItem { index, item, error }),
+    }
+  }
+  (valid, invalid)
+}
+
+/// Same as `get_changed_items`, ignoring the items without a valid key.
+pub fn try_get_changed_items<'a, K, TE, TN>(
+  existing_items: &'a [TE],
+  new_items: &'a [TN],
+) -> TryChangedItems<'a, K, TE, TN>
+where
+  K: Hash + Eq,
+  TE: TryHasItemKey<K>,
+  TN: TryHasItemKey<K>,
+{
+  let (existing, invalid_existing) = partition_keys(existing_items);
+  let (new, invalid_new) = partition_keys(new_items);
+  TryChangedItems {
+    changes: keyed_changed_items(
+      existing.iter().map(|(_, (ei, key))| (*ei, key)),
+      new.iter().map(|(_, (ni, key))| (*ni, key)),
+    ),
+    invalid_existing,
+    invalid_new,
+  }
+}
+
+/// Same as `get_dup_items`, with the items without a valid key.
+pub fn try_get_dup_items<K, T>(items: &[T]) -> (DupItems<'_, T, K>, InvalidItems<'_, T, K>)
+where
+  K: PartialEq + Clone,
+  T: TryHasItemKey<K>,
+{
+  let (valid, invalid) = partition_keys(items);
+  let dups = get_dup_items(&valid)
+    .into_iter()
+    .map(|(key, dups)| {
+      (
+        key,
+        dups
+          .into_iter()
+          .map(|(_, &(index, (item, _)))| (index, item))
+          .collect(),
+      )
+    })
+    .collect();
+  (dups, invalid)
+}
+
+/// Same as `group_by_key` with `try_get_item_key`, with the items without a
+/// valid key.
+pub fn try_group_by_key<K, T>(items: &[T]) -> (KeyGroups<'_, T, K>, InvalidItems<'_, T, K>)
+where
+  K: Hash + Eq,
+  T: TryHasItemKey<K>,
+{
+  let (valid, invalid) = partition_keys(items);
+  let groups = group_keyed(valid.into_iter().map(|(_, pair)| pair));
+  (groups, invalid)
+}
@@ -0,0 +1,176 @@
+//! Keeps the last seen list of each source in a local file, to diff the next
+//! poll against it.
+//!
+//! Snapshots are JSON files named `<source>.snapshot.json`:
+//!
+//! ```json
+//! { "version": 1, "items": [...] }
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::hash::Hash;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::list::{get_changed_items_hashed, ChangeSet, HasItemKey};
+
+/// Version of the snapshot file format.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct SnapshotRef<'a, T> {
+  version: u32,
+  items: &'a [T],
+}
+
+#[derive(Deserialize)]
+struct SnapshotVersion {
+  version: u32,
+}
+
+#[derive(Deserialize)]
+struct SnapshotItems<T> {
+  items: Vec<T>,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+  Io(io::Error),
+  Json(serde_json::Error),
+  /// The file was written by a newer or unknown format version.
+  UnsupportedVersion(u32),
+  /// Source names may only contain ASCII letters, digits, `-`, `_` and `.`.
+  InvalidSource(String),
+}
+
+impl_err_from! {
+  SnapshotError [
+    (io::Error => |e| SnapshotError::Io(e)),
+    (serde_json::Error => |e| SnapshotError::Json(e))
+  ]
+}
+
+impl fmt::Display for SnapshotError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      SnapshotError::Io(ref e) => write!(f, "snapshot io error: {}", e),
+      SnapshotError::Json(ref e) => write!(f, "snapshot json error: {}", e),
+      SnapshotError::UnsupportedVersion(version) => {
+        write!(f, "unsupported snapshot version: {}", version)
+      }
+      SnapshotError::InvalidSource(ref source) => {
+        write!(f, "invalid snapshot source: {:?}", source)
+      }
+    }
+  }
+}
+
+impl Error for SnapshotError {}
+
+/// A directory of snapshots, one file per named source.
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+  dir: PathBuf,
+}
+
+impl SnapshotStore {
+  /// The directory is created on the first save.
+  pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+    SnapshotStore { dir: dir.into() }
+  }
+
+  fn path(&self, source: &str) -> Result<PathBuf, SnapshotError> {
+    let valid = !source.is_empty()
+      && !source.starts_with('.')
+      && source
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+    if !valid {
+      return Err(SnapshotError::InvalidSource(source.to_string()));
+    }
+    Ok(self.dir.join(format!("{}.snapshot.json", source)))
+  }
+
+  /// Returns the last saved items, `None` if the source was never saved.
+  pub fn load<T>(&self, source: &str) -> Result<Option<Vec<T>>, SnapshotError>
+  where
+    T: DeserializeOwned,
+  {
+    let data = match fs::read(self.path(source)?) {
+      Ok(data) => data,
+      Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+      Err(e) => return Err(e.into()),
+    };
+    // check the version before decoding the items
+    let SnapshotVersion { version } = serde_json::from_slice(&data)?;
+    if version != SNAPSHOT_VERSION {
+      return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    let SnapshotItems { items } = serde_json::from_slice(&data)?;
+    Ok(Some(items))
+  }
+
+  /// Replaces the snapshot of the source. The file is written to a temporary
+  /// file first then renamed, so a crash never leaves a partial snapshot.
+  pub fn save<T>(&self, source: &str, items: &[T]) -> Result<(), SnapshotError>
+  where
+    T: Serialize,
+  {
+    let path = self.path(source)?;
+    fs::create_dir_all(&self.dir)?;
+    let tmp_path = path.with_extension("json.tmp");
+    {
+      let mut file = File::create(&tmp_path)?;
+      serde_json::to_writer(
+        &mut file,
+        &SnapshotRef {
+          version: SNAPSHOT_VERSION,
+          items,
+        },
+      )?;
+      file.flush()?;
+      file.sync_all()?;
+    }
+    fs::rename(&tmp_path, &path)?;
+    Ok(())
+  }
+
+  /// Removes the snapshot of the source, if any.
+  pub fn remove(&self, source: &str) -> Result<(), SnapshotError> {
+    match fs::remove_file(self.path(source)?) {
+      Ok(()) => Ok(()),
+      Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+      Err(e) => Err(e.into()),
+    }
+  }
+
+  /// Diffs `items` against the last saved items, without saving them. All
+  /// items are added if the source was never saved.
+  pub fn diff<K, T>(&self, source: &str, items: &[T]) -> Result<ChangeSet<K, T, T>, SnapshotError>
+  where
+    K: Hash + Eq,
+    T: HasItemKey<K> + DeserializeOwned + Clone,
+  {
+    let previous: Vec<T> = self.load(source)?.unwrap_or_default();
+    Ok(get_changed_items_hashed(&previous, items).into())
+  }
+
+  /// Same as `diff`, then saves `items` as the new snapshot.
+  ///
+  /// Use `diff` and `save` instead if the changes must be applied before the
+  /// snapshot moves forward.
+  pub fn poll<K, T>(&self, source: &str, items: &[T]) -> Result<ChangeSet<K, T, T>, SnapshotError>
+  where
+    K: Hash + Eq,
+    T: HasItemKey<K> + Serialize + DeserializeOwned + Clone,
+  {
+    let changes = self.diff(source, items)?;
+    self.save(source, items)?;
+    Ok(changes)
+  }
+}
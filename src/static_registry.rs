@@ -4,6 +4,7 @@ pub struct StaticRegistry<T: Sync + 'static> {
   nodes: RwLock<Vec<T>>,
 }
 
+#[allow(clippy::new_without_default)]
 impl<T: Sync + 'static> StaticRegistry<T> {
   pub fn new() -> Self {
     Self {